@@ -1,3 +1,4 @@
+use crate::command::cache::Cache;
 use crate::command::lsp::Lsp;
 use crate::command::once::Once;
 use log::LevelFilter;
@@ -6,6 +7,7 @@ use log::LevelFilter;
 pub enum Command {
     Lsp(Lsp),
     Once(Once),
+    Cache(Cache),
 }
 
 #[derive(clap::Parser, Debug)]
@@ -42,6 +44,7 @@ impl Cli {
         match self.command {
             Command::Lsp(command) => command.run().await,
             Command::Once(once) => once.run().await,
+            Command::Cache(cache) => cache.run().await,
         }
     }
 }
@@ -0,0 +1,30 @@
+use anyhow::bail;
+use seedwing_enforcer_common::enforcer::cache::disk;
+
+/// Manage the persistent evaluation cache
+#[derive(clap::Args, Debug)]
+pub struct Cache {
+    #[command(subcommand)]
+    command: CacheCommand,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Remove all cached evaluation results
+    Clear,
+}
+
+impl Cache {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self.command {
+            CacheCommand::Clear => {
+                let Some(location) = disk::default_location() else {
+                    bail!("Unable to find the cache directory");
+                };
+                disk::clear(&location)?;
+                log::info!("Cleared cache: {}", location.display());
+                Ok(())
+            }
+        }
+    }
+}
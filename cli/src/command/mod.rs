@@ -1,2 +1,3 @@
+pub mod cache;
 pub mod lsp;
 pub mod once;
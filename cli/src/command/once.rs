@@ -1,15 +1,28 @@
 use crate::util::result_to_markdown;
 use anyhow::{bail, Result};
+use chrono::NaiveDate;
 use clap::{Args, ValueEnum};
 use seedwing_enforcer_common::{
     config::Config,
-    enforcer::{source::AutoSource, Dependency, Enforcer},
-    utils::{pool::Pool, progress::NoProgress},
+    enforcer::{
+        seedwing,
+        source::{
+            sbom::{file::FileGenerator, SBOM},
+            AutoSource, Source,
+        },
+        Dependency, Enforcer, Project,
+    },
+    utils::{
+        pool::Pool,
+        process::{Execution, Trust},
+        progress::NoProgress,
+    },
 };
 use seedwing_policy_engine::{lang::Severity, runtime::Response};
 use serde::Serialize;
 use std::env::current_dir;
 use std::{fmt::Debug, path::PathBuf};
+use tower_lsp::lsp_types::{DiagnosticSeverity, Url};
 
 /// Scan dependencies once
 #[derive(Args, Debug)]
@@ -18,6 +31,13 @@ pub struct Once {
     /// The root of the project. Defaults to the current directory.
     #[arg(short, long)]
     root: Option<PathBuf>,
+    /// Evaluate the dependencies of an SBOM (CycloneDX or SPDX), instead of scanning the project.
+    #[arg(long)]
+    sbom: Option<PathBuf>,
+    /// Trust the project, allowing to run its build tools (like Maven, Gradle, or Cargo), which
+    /// executes code of the project. Otherwise, only lockfiles are read.
+    #[arg(long)]
+    trusted: bool,
     /// The output format
     #[arg(short, long, value_enum, default_value_t = Output::Markdown)]
     output: Output,
@@ -38,46 +58,50 @@ impl Once {
             .await
             .expect("invalid enforcer configuration");
 
-        let dependencies = self.get_deps(enforcer.config.transpose()?).await;
+        let config = enforcer.config.transpose()?;
+        let project = self.get_project(config.clone()).await;
 
-        let result = match dependencies {
-            Err(err) => {
-                let msg = format!("{:?}", err);
-                Outcome {
-                    status: AggregatedResult::ConfigError(msg),
-                    details: vec![],
-                }
-            }
-            Ok(dependencies) => match enforcer.evaluator.eval(dependencies, NoProgress).await {
-                Ok(scan) => {
-                    let mut error = false;
-                    let mut result = Vec::new();
-                    for (dep, outcome) in scan {
-                        result.push(PolicyResult::new(dep, &outcome));
-                        if outcome.severity == Severity::Error {
-                            error = true;
-                        }
-                    }
-                    if error {
-                        Outcome {
-                            status: AggregatedResult::Rejected,
-                            details: result,
+        let result = match project {
+            Err(err) => Outcome::error(format!("{:?}", err)),
+            Ok((project, modules)) => {
+                #[allow(deprecated)]
+                let evaluator = &enforcer.evaluator;
+                let evaluation = async {
+                    let scan = evaluator
+                        .eval(project.dependencies.clone(), NoProgress)
+                        .await?;
+                    let project = evaluator.eval_project(project).await?;
+                    Ok::<_, seedwing::Error>((scan, project))
+                };
+
+                match evaluation.await {
+                    Ok((scan, project)) => {
+                        let mut error = project
+                            .as_ref()
+                            .map(|response| response.severity == Severity::Error)
+                            .unwrap_or_default();
+                        let mut result = Vec::new();
+                        for (dep, outcome) in scan {
+                            result.push(PolicyResult::new(dep, &outcome, config.as_ref()));
+                            if outcome.severity == Severity::Error {
+                                error = true;
+                            }
                         }
-                    } else {
                         Outcome {
-                            status: AggregatedResult::Accepted,
+                            status: match error {
+                                true => AggregatedResult::Rejected,
+                                false => AggregatedResult::Accepted,
+                            },
+                            project,
+                            modules,
                             details: result,
                         }
                     }
-                }
-                Err(e) => {
-                    let msg = format!("Error while scanning dependencies : {:?}", e);
-                    Outcome {
-                        status: AggregatedResult::ConfigError(msg),
-                        details: vec![],
+                    Err(e) => {
+                        Outcome::error(format!("Error while scanning dependencies : {:?}", e))
                     }
                 }
-            },
+            }
         };
 
         match self.output {
@@ -95,10 +119,49 @@ impl Once {
         }
     }
 
-    async fn get_deps(&self, config: Option<Config>) -> Result<Vec<Dependency>> {
-        let path = self.root.clone().unwrap_or(PathBuf::from("./"));
-        let source = AutoSource::find_source(path, config).await?;
-        source.scan().await
+    /// Scan the project, and group its dependencies by module, if there is more than one
+    async fn get_project(&self, config: Option<Config>) -> Result<(Project, Vec<ModuleOutcome>)> {
+        let source: Box<dyn Source> = match &self.sbom {
+            Some(sbom) => Box::new(SBOM::new(FileGenerator::new(sbom))),
+            None => {
+                let path = self.root.clone().unwrap_or(PathBuf::from("./"));
+                let trust = if self.trusted {
+                    Trust::Trusted
+                } else {
+                    Trust::Untrusted
+                };
+                let execution = Execution::default().with_trust(trust);
+                AutoSource::find_source(path, config, execution, None).await?
+            }
+        };
+        let dependencies = source.scan().await?;
+        let graph = source.graph();
+
+        let modules = source.modules();
+        let modules = if modules.len() > 1 {
+            modules
+                .into_iter()
+                .map(|module| ModuleOutcome {
+                    dependencies: dependencies
+                        .iter()
+                        .filter(|d| graph.roots_of(&d.purl).contains(&module.purl))
+                        .map(|d| d.purl.clone())
+                        .collect(),
+                    purl: module.purl,
+                    manifest: module.manifest,
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Ok((
+            Project {
+                roots: graph.roots().cloned().collect(),
+                dependencies,
+            },
+            modules,
+        ))
     }
 
     async fn enforcer_setup(&self) -> Result<Enforcer> {
@@ -109,13 +172,19 @@ impl Once {
         let enforcer = Enforcer::new(root, Pool::new()).await;
 
         let diag = enforcer.diagnostics().await;
-        if !diag.is_empty() {
-            for (path, issue) in diag {
-                println!("{}", path.to_string_lossy());
-                for i in issue {
-                    println!("\t - {}", i.message)
-                }
+        let failed = diag
+            .values()
+            .flatten()
+            .any(|d| d.severity == Some(DiagnosticSeverity::ERROR));
+
+        for (path, issue) in diag {
+            println!("{}", path.to_string_lossy());
+            for i in issue {
+                println!("\t - {}", i.message)
             }
+        }
+
+        if failed {
             bail!("")
         } else {
             Ok(enforcer)
@@ -126,14 +195,50 @@ impl Once {
 #[derive(Debug, Serialize)]
 pub struct Outcome {
     pub status: AggregatedResult,
+    /// The result of the project rule, if configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<Response>,
+    /// The modules of a multi-module project
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<ModuleOutcome>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub details: Vec<PolicyResult>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ModuleOutcome {
+    pub purl: Url,
+    pub manifest: PathBuf,
+    /// The Package URLs of the dependencies the module pulls in
+    pub dependencies: Vec<Url>,
+}
+
+impl Outcome {
+    fn error(msg: String) -> Self {
+        Self {
+            status: AggregatedResult::ConfigError(msg),
+            project: None,
+            modules: vec![],
+            details: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct PolicyResult {
     pub dependency: Dependency,
     pub response: Response,
+    /// Rules which failed, but were waived by an exception
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub waived: Vec<Waived>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Waived {
+    pub rule: String,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<NaiveDate>,
 }
 
 #[derive(Debug, Serialize)]
@@ -144,10 +249,37 @@ pub enum AggregatedResult {
 }
 
 impl PolicyResult {
-    pub fn new(dependency: Dependency, response: &Response) -> PolicyResult {
+    pub fn new(
+        dependency: Dependency,
+        response: &Response,
+        config: Option<&Config>,
+    ) -> PolicyResult {
+        let waived = config
+            .map(|config| {
+                response
+                    .rationale
+                    .iter()
+                    .filter_map(|rule| {
+                        let exception = seedwing::waiver(config, &dependency, rule)?;
+                        Some(Waived {
+                            rule: rule.name.to_string(),
+                            reason: exception.reason.clone(),
+                            expires: exception.expires,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         PolicyResult {
             dependency,
             response: response.clone(),
+            waived,
         }
     }
+
+    /// Get the waiver of a rule, if it was waived
+    pub fn waiver(&self, rule: &str) -> Option<&Waived> {
+        self.waived.iter().find(|waived| waived.rule == rule)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::command::once::{AggregatedResult, Outcome};
+use crate::command::once::{AggregatedResult, Outcome, PolicyResult};
 use seedwing_policy_engine::lang::Severity;
 use seedwing_policy_engine::runtime::response::Collector;
 
@@ -19,45 +19,106 @@ pub fn result_to_markdown(data: &Outcome) -> String {
 
     markdown.push_str("\n\n");
 
+    // The project as a whole, if there is a project rule
+    if let Some(project) = &data.project {
+        markdown.push_str("## Project\n\n");
+        markdown.push_str("| Satisfied | Reason |\n");
+        markdown.push_str("| --------- | ------ |\n");
+
+        let reasons = Collector::new(project)
+            .highest_severity()
+            .collect()
+            .into_iter()
+            .map(|resp| format!("`{}` : {}", resp.name, resp.reason))
+            .collect::<Vec<_>>()
+            .join("<br>");
+
+        markdown.push_str(&format!(
+            "| {} | {reasons} |\n\n",
+            severity_as_emoji(project.severity)
+        ));
+
+        if data.modules.is_empty() {
+            markdown.push_str("## Dependencies\n\n");
+        }
+    }
+
+    if data.modules.is_empty() {
+        push_dependencies(&mut markdown, data.details.iter());
+    } else {
+        // one section for each module, with the dependencies it pulls in
+        for module in &data.modules {
+            markdown.push_str(&format!(
+                "## Module `{}`\n\n*{}*\n\n",
+                module.purl,
+                module.manifest.display()
+            ));
+            push_dependencies(
+                &mut markdown,
+                data.details
+                    .iter()
+                    .filter(|result| module.dependencies.contains(&result.dependency.purl)),
+            );
+            markdown.push('\n');
+        }
+    }
+
+    markdown
+}
+
+fn push_dependencies<'a>(markdown: &mut String, results: impl Iterator<Item = &'a PolicyResult>) {
     // Define table header
-    markdown.push_str("| Satisfied | Package URL | Reason |\n");
-    markdown.push_str("| --------- | ----------- | ------ |\n");
+    markdown.push_str("| Satisfied | Package URL | Rule | Reason |\n");
+    markdown.push_str("| --------- | ----------- | ---- | ------ |\n");
 
-    // Populate the table with dependencies
-    for result in &data.details {
+    // Populate the table with dependencies, one row for each rule which isn't satisfied
+    for result in results {
         match result.response.severity {
             Severity::None => markdown.push_str(&format!(
-                "| {} | {} | | \n",
+                "| {} | {} | | | \n",
                 severity_as_emoji(Severity::None),
                 &result.dependency.purl
             )),
-            severity => {
-                let resp = Collector::new(&result.response)
-                    .highest_severity()
-                    .collect();
-
-                let reasons = resp
-                    .into_iter()
-                    .map(|resp| {
-                        format!(
-                            "`{name}` : {reason}",
-                            name = resp.name,
-                            reason = resp.reason
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join("<br>");
-
-                markdown.push_str(&format!(
-                    "| {} | {} | {reasons} | \n",
-                    severity_as_emoji(severity),
-                    &result.dependency.purl
-                ))
+            _ => {
+                for rule in &result.response.rationale {
+                    if rule.severity == Severity::None {
+                        continue;
+                    }
+
+                    let resp = Collector::new(rule).highest_severity().collect();
+
+                    let reasons = resp
+                        .into_iter()
+                        .map(|resp| {
+                            format!(
+                                "`{name}` : {reason}",
+                                name = resp.name,
+                                reason = resp.reason
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("<br>");
+
+                    let reasons = match result.waiver(&rule.name.to_string()) {
+                        Some(waived) => format!("*waived*: {}<br>{reasons}", waived.reason),
+                        None => reasons,
+                    };
+
+                    let reasons = match result.dependency.path() {
+                        Some(path) => format!("{reasons}<br>*via*: `{path}`"),
+                        None => reasons,
+                    };
+
+                    markdown.push_str(&format!(
+                        "| {} | {} | {} | {reasons} | \n",
+                        severity_as_emoji(rule.severity),
+                        &result.dependency.purl,
+                        rule.name,
+                    ))
+                }
             }
         }
     }
-
-    markdown
 }
 
 fn severity_as_emoji(severity: Severity) -> &'static str {
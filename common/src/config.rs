@@ -1,8 +1,27 @@
 //! Configuration
 
-use std::{fs, io, path::Path};
+use crate::utils::{
+    projects::{
+        is_requirements_file, requirements_files, CARGO_FILE, CARGO_LOCK_FILE, GO_MOD_FILE,
+        GO_SUM_FILE, GRADLE_BUILDSCRIPT_LOCK_FILE, GRADLE_DIR, GRADLE_FILE, GRADLE_KTS_FILE,
+        GRADLE_LOCK_FILE, GRADLE_PROPERTIES_FILE, GRADLE_SETTINGS_FILE, GRADLE_SETTINGS_KTS_FILE,
+        GRADLE_VERSION_CATALOG, MAVEN_CONFIG_DIR, MAVEN_FILE, NPM_FILE, NPM_LOCK_FILE, PIPFILE,
+        PIPFILE_LOCK, PNPM_LOCK_FILE, POETRY_LOCK_FILE, PYPROJECT_FILE, REQUIREMENTS_FILE,
+        UV_LOCK_FILE, YARN_LOCK_FILE,
+    },
+    wildcard,
+};
+use anyhow::bail;
+use chrono::{NaiveDate, Utc};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 pub const FILE_NAME_YAML: &str = ".enforcer.yaml";
+/// An optional file, next to the configuration, holding additional exceptions
+pub const FILE_NAME_EXCEPTIONS_YAML: &str = ".enforcer-exceptions.yaml";
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Config {
@@ -10,31 +29,370 @@ pub struct Config {
     pub dependencies: Option<Dependencies>,
     #[serde(default)]
     pub enforcer: EnforcerConfig,
+    /// Waived rule violations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exceptions: Vec<Exception>,
 }
 
+impl Config {
+    /// Find the active exception waiving a rule for a Package URL.
+    ///
+    /// Expired exceptions are ignored.
+    pub fn waiver(&self, rule: &str, purl: &str) -> Option<&Exception> {
+        self.exceptions
+            .iter()
+            .find(|exception| !exception.is_expired() && exception.matches(rule, purl))
+    }
+}
+
+/// The name of the rule defined by the top-level `policy` and `requires` fields.
+pub const DEFAULT_RULE: &str = "default";
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Dependencies {
+    /// The policy of a single, unnamed, rule
+    ///
+    /// This is the short form of a rule named [`DEFAULT_RULE`]. Once loaded, it will be part of
+    /// the `rules`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    /// The pattern of a single, unnamed, rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+
+    /// The rules, all dependencies get evaluated against
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+
+    /// Directories of data files, available to all policies through `data::from`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data: Vec<String>,
+
+    /// A rule evaluating the project as a whole, rather than each dependency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<ProjectRule>,
+}
+
+/// The name of the project rule, used for reporting.
+pub const PROJECT_RULE: &str = "project";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProjectRule {
+    /// The policy file
     pub policy: String,
+    /// The pattern the project, with all its dependencies, must satisfy
     pub requires: String,
+    /// Override the severity of a rule which is not satisfied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Rule {
+    /// The name of the rule, used for reporting
+    pub name: String,
+    /// The policy file
+    pub policy: String,
+    /// The pattern a dependency must satisfy
+    pub requires: String,
+    /// Override the severity of a rule which is not satisfied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    /// Only evaluate dependencies with a Package URL matching this pattern (using `*` as wildcard)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Advice,
+    Warning,
+    Error,
+}
+
+/// An exception to a rule, waiving its violations for some packages
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Exception {
+    /// The Package URLs this exception applies to (using `*` as wildcard)
+    pub purl: String,
+    /// The name of the rule which is waived, all rules if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// The justification of the exception
+    pub reason: String,
+    /// The last day the exception is valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<NaiveDate>,
+    /// The file the exception was declared in
+    #[serde(skip)]
+    pub source: PathBuf,
+}
+
+impl Exception {
+    pub fn matches(&self, rule: &str, purl: &str) -> bool {
+        self.rule.as_deref().map(|r| r == rule).unwrap_or(true)
+            && wildcard::matches(&self.purl, purl)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires
+            .map(|expires| expires < Utc::now().date_naive())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct EnforcerConfig {
     pub source: Option<ManifestType>,
+    /// The SBOM file to read, for the `sbom` source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sbom: Option<String>,
+    /// The number of workers evaluating dependencies in parallel, defaults to one
+    ///
+    /// Each worker builds its own copy of the policies. The number is limited to the size of the
+    /// pool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workers: Option<usize>,
+    /// The persistent cache of evaluation results
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// Settings of the `Maven` source
+    #[serde(default)]
+    pub maven: MavenConfig,
+    /// Settings of the sources running a build tool, like Maven or Gradle
+    #[serde(default)]
+    pub generator: GeneratorConfig,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GeneratorConfig {
+    /// The time (in seconds) after which the build gets killed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MavenConfig {
+    /// How dependencies get resolved
+    #[serde(default)]
+    pub resolver: MavenResolver,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MavenResolver {
+    /// Read the POMs of the local repository, without running Maven
+    Native,
+    /// Run Maven, with the CycloneDX plugin
+    #[default]
+    Plugin,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheConfig {
+    /// Keep evaluation results on disk, between runs
+    #[serde(default)]
+    pub persistent: bool,
+    /// The time (in seconds) until a cached result expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    /// The maximum size (in bytes) of the persistent cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ManifestType {
     Cargo,
     Maven,
+    Npm,
+    Python,
+    Go,
+    Gradle,
+    /// An existing SBOM, rather than a project
+    Sbom,
 }
 
-/// resolve the paths in the configuration
-fn resolve(mut config: Config, path: &Path) -> Config {
+impl ManifestType {
+    /// The file declaring the dependencies, which also receives the diagnostics.
+    pub fn manifest(&self) -> &'static str {
+        match self {
+            Self::Cargo => CARGO_FILE,
+            Self::Maven => MAVEN_FILE,
+            Self::Npm => NPM_FILE,
+            Self::Python => PYPROJECT_FILE,
+            Self::Go => GO_MOD_FILE,
+            Self::Gradle => GRADLE_FILE,
+            // diagnostics go to the configuration, which points to the SBOM
+            Self::Sbom => FILE_NAME_YAML,
+        }
+    }
+
+    /// The path of the manifest of a project.
+    ///
+    /// Some projects may use an alternative file instead, like the Kotlin variant of the Gradle
+    /// build file, or a Python project only having a `Pipfile` or `requirements*.txt` files.
+    pub fn manifest_path(&self, root: &Path) -> PathBuf {
+        let alternatives: &[&str] = match self {
+            Self::Gradle => &[GRADLE_KTS_FILE],
+            Self::Python => &[PYPROJECT_FILE, PIPFILE, REQUIREMENTS_FILE],
+            _ => &[],
+        };
+
+        alternatives
+            .iter()
+            .map(|file| root.join(file))
+            .find(|path| path.exists())
+            .or_else(|| match self {
+                Self::Python => requirements_files(root).ok()?.into_iter().next(),
+                _ => None,
+            })
+            .unwrap_or_else(|| root.join(self.manifest()))
+    }
+
+    /// All existing files of a project which have an impact on the scanned dependencies.
+    ///
+    /// Next to the [`Self::files`], this includes files matching a pattern, like the
+    /// `requirements*.txt` files of a Python project.
+    pub fn paths(&self, root: &Path) -> Vec<PathBuf> {
+        let mut paths = self
+            .files()
+            .iter()
+            .map(|file| root.join(file))
+            .collect::<Vec<_>>();
+
+        if let Self::Python = self {
+            for path in requirements_files(root).unwrap_or_default() {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Check if a file has an impact on the scanned dependencies of a project.
+    ///
+    /// Unlike [`Self::paths`], this also matches files which don't exist yet.
+    pub fn is_file(&self, root: &Path, path: &Path) -> bool {
+        let matches_pattern = match self {
+            Self::Python => {
+                path.parent() == Some(root)
+                    && path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .map(is_requirements_file)
+                        .unwrap_or_default()
+            }
+            // the configuration of the build tool, which is part of the generated SBOM's inputs
+            Self::Maven => path.starts_with(root.join(MAVEN_CONFIG_DIR)),
+            Self::Gradle => path.starts_with(root.join(GRADLE_DIR)),
+            _ => false,
+        };
+
+        matches_pattern || self.files().iter().any(|file| root.join(file) == path)
+    }
+
+    /// All files, next to the manifest, which have an impact on the scanned dependencies.
+    pub fn files(&self) -> &'static [&'static str] {
+        match self {
+            Self::Cargo => &[CARGO_FILE, CARGO_LOCK_FILE],
+            Self::Maven => &[MAVEN_FILE],
+            Self::Npm => &[NPM_FILE, NPM_LOCK_FILE, YARN_LOCK_FILE, PNPM_LOCK_FILE],
+            Self::Python => &[
+                PYPROJECT_FILE,
+                POETRY_LOCK_FILE,
+                UV_LOCK_FILE,
+                PIPFILE,
+                PIPFILE_LOCK,
+                REQUIREMENTS_FILE,
+            ],
+            Self::Go => &[GO_MOD_FILE, GO_SUM_FILE],
+            Self::Gradle => &[
+                GRADLE_FILE,
+                GRADLE_KTS_FILE,
+                GRADLE_SETTINGS_FILE,
+                GRADLE_SETTINGS_KTS_FILE,
+                GRADLE_PROPERTIES_FILE,
+                GRADLE_LOCK_FILE,
+                GRADLE_BUILDSCRIPT_LOCK_FILE,
+                GRADLE_VERSION_CATALOG,
+            ],
+            // changes to the configuration re-evaluate all, the SBOM is tracked on its own
+            Self::Sbom => &[],
+        }
+    }
+}
+
+/// resolve the rules and paths in the configuration
+fn resolve(mut config: Config, path: &Path) -> anyhow::Result<Config> {
     if let Some(deps) = &mut config.dependencies {
-        deps.policy = path.join(&deps.policy).to_string_lossy().to_string()
+        match (deps.policy.take(), deps.requires.take()) {
+            (Some(policy), Some(requires)) => deps.rules.insert(
+                0,
+                Rule {
+                    name: DEFAULT_RULE.to_string(),
+                    policy,
+                    requires,
+                    ..Default::default()
+                },
+            ),
+            (None, None) => {}
+            _ => bail!("'policy' and 'requires' must be used together"),
+        }
+
+        let mut names = HashSet::new();
+        for rule in &mut deps.rules {
+            if !names.insert(rule.name.clone()) {
+                bail!("Duplicate rule name: {}", rule.name);
+            }
+            rule.policy = path.join(&rule.policy).to_string_lossy().to_string();
+        }
+
+        for data in &mut deps.data {
+            *data = path.join(&*data).to_string_lossy().to_string();
+        }
+
+        if let Some(project) = &mut deps.project {
+            project.policy = path.join(&project.policy).to_string_lossy().to_string();
+        }
+    }
+
+    if let Some(sbom) = &mut config.enforcer.sbom {
+        *sbom = path.join(&*sbom).to_string_lossy().to_string();
+    }
+
+    for exception in &mut config.exceptions {
+        exception.source = path.join(FILE_NAME_YAML);
+    }
+    config.exceptions.extend(load_exceptions(path)?);
+
+    for exception in &config.exceptions {
+        if exception.reason.trim().is_empty() {
+            bail!("Exception for '{}' is missing a reason", exception.purl);
+        }
+    }
+
+    Ok(config)
+}
+
+/// load the exceptions from the dedicated file, if it exists
+fn load_exceptions(path: &Path) -> anyhow::Result<Vec<Exception>> {
+    let source = path.join(FILE_NAME_EXCEPTIONS_YAML);
+    match fs::File::open(&source) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+        Ok(file) => {
+            let mut exceptions: Vec<Exception> = serde_yaml::from_reader(&file)?;
+            for exception in &mut exceptions {
+                exception.source = source.clone();
+            }
+            Ok(exceptions)
+        }
     }
-    config
 }
 
 /// try loading a configuration in a specific path
@@ -47,7 +405,76 @@ pub async fn try_load(dir: &Path) -> Option<anyhow::Result<Config>> {
         Ok(file) => Some(
             serde_yaml::from_reader(&file)
                 .map_err(|err| err.into())
-                .map(|c| resolve(c, dir)),
+                .and_then(|c| resolve(c, dir)),
         ),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_waiver() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+exceptions:
+  - purl: "pkg:cargo/openssl@*"
+    rule: license
+    reason: Reviewed by legal
+  - purl: "pkg:cargo/time@0.1.*"
+    reason: Not affected
+    expires: 2000-01-01
+"#,
+        )
+        .unwrap();
+
+        assert!(config
+            .waiver("license", "pkg:cargo/openssl@0.10.0")
+            .is_some());
+        assert!(config.waiver("cve", "pkg:cargo/openssl@0.10.0").is_none());
+        // expired
+        assert!(config.waiver("cve", "pkg:cargo/time@0.1.45").is_none());
+    }
+
+    #[test]
+    fn test_python_files() {
+        let root = std::env::temp_dir().join(format!(
+            "seedwing-enforcer-test-python-files-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("requirements-dev.txt"), "pytest==7.4.0\n").unwrap();
+
+        let python = ManifestType::Python;
+        assert_eq!(
+            python.manifest_path(&root),
+            root.join("requirements-dev.txt")
+        );
+        assert!(python
+            .paths(&root)
+            .contains(&root.join("requirements-dev.txt")));
+        assert!(python.is_file(&root, &root.join("requirements-test.txt")));
+        assert!(python.is_file(&root, &root.join(PIPFILE_LOCK)));
+        assert!(!python.is_file(&root, &root.join("sub/requirements.txt")));
+
+        fs::write(root.join(PIPFILE), "").unwrap();
+        assert_eq!(python.manifest_path(&root), root.join(PIPFILE));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_build_tool_files() {
+        let root = Path::new("/project");
+
+        let maven = ManifestType::Maven;
+        assert!(maven.is_file(root, &root.join(".mvn/extensions.xml")));
+        assert!(!maven.is_file(root, &root.join("sub/.mvn/extensions.xml")));
+
+        let gradle = ManifestType::Gradle;
+        assert!(gradle.is_file(root, &root.join(GRADLE_PROPERTIES_FILE)));
+        assert!(gradle.is_file(root, &root.join("gradle/wrapper/gradle-wrapper.properties")));
+    }
+}
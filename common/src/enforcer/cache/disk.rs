@@ -0,0 +1,335 @@
+//! A persistent cache, stored on disk
+//!
+//! Entries are stored in a directory per digest of the policies, so that changing a policy (or
+//! its data) doesn't require to clear the cache.
+
+use super::Cache;
+use crate::{config::Dependencies, enforcer::Dependency};
+use seedwing_policy_engine::runtime::Response;
+use sha2::{Digest, Sha256};
+use std::{
+    cmp::Reverse,
+    fs,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Time until a cached result expires, unless configured otherwise
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Maximum size (in bytes) of the cache, unless configured otherwise
+pub const DEFAULT_MAX_SIZE: u64 = 100 * 1024 * 1024;
+/// Minimum time between two runs of [`prune_periodically`]
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The last time the cache was pruned by this process
+static LAST_PRUNE: Mutex<Option<Instant>> = Mutex::new(None);
+/// Makes the names of temporary files unique within the process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The default location of the persistent cache, in the user's cache directory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_location() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("seedwing-enforcer"))
+}
+
+/// The default location of the persistent cache, in the user's cache directory
+#[cfg(target_arch = "wasm32")]
+pub fn default_location() -> Option<PathBuf> {
+    None
+}
+
+/// Remove all entries of the cache
+pub fn clear(base: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(base) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// [`prune`] the cache, unless that was already done by this process within the [`PRUNE_INTERVAL`].
+///
+/// This walks the whole cache, so it shouldn't be done on every change of the configuration.
+pub fn prune_periodically(base: &Path, ttl: Duration, max_size: u64) -> io::Result<()> {
+    {
+        let mut last = LAST_PRUNE.lock().unwrap_or_else(|err| err.into_inner());
+        if matches!(*last, Some(last) if last.elapsed() < PRUNE_INTERVAL) {
+            return Ok(());
+        }
+        *last = Some(Instant::now());
+    }
+
+    prune(base, ttl, max_size)
+}
+
+/// Remove expired entries, and then the oldest ones, until the cache fits into its size limit.
+///
+/// Entries which fail to be pruned are logged and skipped, as other processes might use the
+/// cache at the same time.
+pub fn prune(base: &Path, ttl: Duration, max_size: u64) -> io::Result<()> {
+    let dirs = match fs::read_dir(base) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        result => result?,
+    };
+
+    let mut entries = vec![];
+
+    for dir in dirs {
+        let dir = match dir {
+            Ok(dir) => dir.path(),
+            Err(err) => {
+                log::warn!("Failed to read cache directory {}: {err}", base.display());
+                continue;
+            }
+        };
+        if !dir.is_dir() {
+            continue;
+        }
+
+        if let Err(err) = prune_expired(&dir, ttl, &mut entries) {
+            log::warn!("Failed to prune cache directory {}: {err}", dir.display());
+        }
+    }
+
+    // keep the newest entries
+    entries.sort_unstable_by_key(|(modified, _, _)| Reverse(*modified));
+
+    let mut size = 0;
+    for (_, len, path) in entries {
+        size += len;
+        if size > max_size {
+            if let Err(err) = fs::remove_file(&path) {
+                log::warn!("Failed to remove cache entry {}: {err}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove the expired entries of a directory, collecting the remaining ones.
+///
+/// The directory is removed as well, once it is empty.
+fn prune_expired(
+    dir: &Path,
+    ttl: Duration,
+    entries: &mut Vec<(SystemTime, u64, PathBuf)>,
+) -> io::Result<()> {
+    let mut empty = true;
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry.and_then(|entry| {
+            let metadata = entry.metadata()?;
+            Ok((metadata.modified()?, metadata.len(), entry.path()))
+        });
+
+        match entry {
+            Ok((modified, _, path)) if is_expired(modified, ttl) => {
+                if let Err(err) = fs::remove_file(&path) {
+                    log::warn!("Failed to remove cache entry {}: {err}", path.display());
+                    empty = false;
+                }
+            }
+            Ok(entry) => {
+                entries.push(entry);
+                empty = false;
+            }
+            Err(err) => {
+                log::warn!("Failed to read cache entry in {}: {err}", dir.display());
+                empty = false;
+            }
+        }
+    }
+
+    if empty {
+        fs::remove_dir(dir)?;
+    }
+
+    Ok(())
+}
+
+/// Compute the digest of the rules, their policies, and the data.
+pub fn digest(root: &Path, dependencies: &Dependencies) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(dependencies)?);
+
+    for rule in &dependencies.rules {
+        digest_path(&mut hasher, &root.join(&rule.policy))?;
+    }
+    for data in &dependencies.data {
+        digest_path(&mut hasher, &root.join(data))?;
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Compute the digest of files, and the content of directories.
+pub fn digest_paths(paths: &[PathBuf]) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+
+    for path in paths {
+        digest_path(&mut hasher, path)?;
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn digest_path(hasher: &mut Sha256, path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            digest_path(hasher, &entry)?;
+        }
+    } else {
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update(fs::read(path)?);
+    }
+
+    Ok(())
+}
+
+fn is_expired(modified: SystemTime, ttl: Duration) -> bool {
+    modified
+        .elapsed()
+        .map(|elapsed| elapsed > ttl)
+        .unwrap_or_default()
+}
+
+/// A cache, persisting the results of one set of policies
+#[derive(Clone, Debug)]
+pub struct DiskCache {
+    /// The directory of the policy digest
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DiskCache {
+    pub fn new(base: &Path, digest: &str, ttl: Duration) -> Self {
+        Self {
+            dir: base.join(digest),
+            ttl,
+        }
+    }
+
+    fn path(&self, dependency: &Dependency) -> PathBuf {
+        let key = Sha256::digest(dependency.cache_key());
+        self.dir.join(format!("{}.json", hex::encode(key)))
+    }
+
+    fn read(&self, path: &Path) -> anyhow::Result<Option<Response>> {
+        let modified = match fs::metadata(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            result => result?.modified()?,
+        };
+
+        if is_expired(modified, self.ttl) {
+            fs::remove_file(path)?;
+            return Ok(None);
+        }
+
+        let file = BufReader::new(fs::File::open(path)?);
+        Ok(Some(serde_json::from_reader(file)?))
+    }
+
+    fn write(&self, dependency: &Dependency, response: &Response) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        // write to a temporary file first, so that readers never see a partial entry. the name
+        // must be unique, as other processes might write the same entry at the same time.
+        let path = self.path(dependency);
+        let temp = path.with_extension(format!(
+            "{}.{}.tmp",
+            process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut file = BufWriter::new(fs::File::create(&temp)?);
+        serde_json::to_writer(&mut file, response)?;
+        file.flush()?;
+        drop(file);
+
+        fs::rename(temp, path)?;
+
+        Ok(())
+    }
+}
+
+impl Cache for DiskCache {
+    fn get(&self, dependency: &Dependency) -> Option<Response> {
+        let path = self.path(dependency);
+        match self.read(&path) {
+            Ok(response) => response,
+            Err(err) => {
+                log::info!("Dropping cache entry {}: {err}", path.display());
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    fn store(&self, dependency: &Dependency, response: Response) {
+        if let Err(err) = self.write(dependency, &response) {
+            log::warn!("Failed to persist cache entry of {dependency}: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use url::Url;
+
+    #[test]
+    fn test_store_and_prune() {
+        let base =
+            std::env::temp_dir().join(format!("seedwing-enforcer-test-cache-{}", process::id()));
+        clear(&base).unwrap();
+
+        let cache = DiskCache::new(&base, "digest", DEFAULT_TTL);
+        let dependency = Dependency::new(Url::parse("pkg:cargo/serde@1.0.160").unwrap());
+
+        assert!(cache.get(&dependency).is_none());
+        cache.store(
+            &dependency,
+            Response {
+                reason: "cached".to_string(),
+                ..Default::default()
+            },
+        );
+        assert_eq!(cache.get(&dependency).unwrap().reason, "cached");
+
+        // nothing fits into an empty cache
+        prune(&base, DEFAULT_TTL, 0).unwrap();
+        assert!(cache.get(&dependency).is_none());
+
+        clear(&base).unwrap();
+    }
+
+    #[test]
+    fn test_digest_paths() {
+        let dir =
+            std::env::temp_dir().join(format!("seedwing-enforcer-test-digest-{}", process::id()));
+        clear(&dir).unwrap();
+        fs::create_dir_all(dir.join(".mvn")).unwrap();
+        fs::write(dir.join("pom.xml"), "<project/>").unwrap();
+        fs::write(dir.join(".mvn/maven.config"), "-T 4").unwrap();
+
+        let paths = [dir.join("pom.xml"), dir.join(".mvn")];
+        let digest = digest_paths(&paths).unwrap();
+        assert_eq!(digest, digest_paths(&paths).unwrap());
+
+        // the content of directories counts as well
+        fs::write(dir.join(".mvn/maven.config"), "-T 8").unwrap();
+        assert_ne!(digest, digest_paths(&paths).unwrap());
+
+        clear(&dir).unwrap();
+    }
+}
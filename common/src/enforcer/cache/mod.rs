@@ -0,0 +1,81 @@
+use crate::enforcer::Dependency;
+use seedwing_policy_engine::runtime::Response;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+pub mod disk;
+
+/// A cache for dependency evaluations
+pub trait Cache: Send {
+    fn get(&self, dependency: &Dependency) -> Option<Response>;
+    fn store(&self, dependency: &Dependency, response: Response);
+}
+
+pub struct NoCache;
+
+impl Cache for NoCache {
+    fn get(&self, _: &Dependency) -> Option<Response> {
+        None
+    }
+
+    fn store(&self, _: &Dependency, _: Response) {}
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DefaultCache {
+    store: Arc<RwLock<HashMap<String, Response>>>,
+}
+
+impl DefaultCache {
+    pub fn invalidate(&self) {
+        self.store.write().unwrap().clear();
+    }
+}
+
+impl Cache for DefaultCache {
+    fn get(&self, dependency: &Dependency) -> Option<Response> {
+        self.store
+            .read()
+            .unwrap()
+            .get(&dependency.cache_key())
+            .cloned()
+    }
+
+    fn store(&self, dependency: &Dependency, response: Response) {
+        self.store
+            .write()
+            .unwrap()
+            .insert(dependency.cache_key(), response);
+    }
+}
+
+impl<C: Cache> Cache for Option<C> {
+    fn get(&self, dependency: &Dependency) -> Option<Response> {
+        self.as_ref()?.get(dependency)
+    }
+
+    fn store(&self, dependency: &Dependency, response: Response) {
+        if let Some(cache) = self {
+            cache.store(dependency, response);
+        }
+    }
+}
+
+/// Two caches, asking the second one only if the first one doesn't have an entry.
+#[derive(Clone, Debug)]
+pub struct Layered<A, B>(pub A, pub B);
+
+impl<A: Cache, B: Cache> Cache for Layered<A, B> {
+    fn get(&self, dependency: &Dependency) -> Option<Response> {
+        self.0.get(dependency).or_else(|| {
+            let response = self.1.get(dependency)?;
+            self.0.store(dependency, response.clone());
+            Some(response)
+        })
+    }
+
+    fn store(&self, dependency: &Dependency, response: Response) {
+        self.1.store(dependency, response.clone());
+        self.0.store(dependency, response);
+    }
+}
@@ -6,16 +6,131 @@ use url::Url;
 
 /// The internal representation of a dependency
 ///
-/// Currently this is exactly a Package URL. However, we could (and should) add additional
-/// information (like scope: dev, build, or regular dependency).
+/// Next to the Package URL, sources may provide additional information, which is passed on to
+/// the policies. Information which a source can't provide is left empty.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Dependency {
     pub purl: Url,
+    /// How the dependency is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<Scope>,
+    /// If the project declares the dependency itself, rather than getting it through another one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direct: Option<bool>,
+    /// The length of the shortest path from the project to the dependency, `1` being a direct one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    /// The direct dependencies which pull in the dependency, the nearest ones first
+    #[serde(
+        default,
+        rename = "introducedBy",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub introduced_by: Vec<Url>,
+    /// The paths through which the dependency gets pulled in, the shortest one first
+    ///
+    /// Each path starts with a direct dependency, and ends with the dependency itself.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<Vec<Url>>,
+    /// Where the dependency is retrieved from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checksums: Vec<Checksum>,
+    /// The declared licenses, either SPDX identifiers, expressions, or names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Scope {
+    Compile,
+    Provided,
+    Runtime,
+    Test,
+    System,
+    /// Development dependency, e.g. Cargo `dev-dependencies`
+    Dev,
+    /// Build dependency, e.g. Cargo `build-dependencies`
+    Build,
+    /// Optional dependency, e.g. npm `optionalDependencies`
+    Optional,
+    /// Dependency expected to be provided by the dependent, e.g. npm `peerDependencies`
+    Peer,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Origin {
+    /// A package registry, the default one if the URL is missing
+    Registry {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    /// A git repository
+    Git {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        revision: Option<String>,
+    },
+    /// A local path
+    Path {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+    /// Another package, used in place of the declared one, e.g. by a Go `replace` directive
+    Replaced { purl: Url },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Checksum {
+    /// The algorithm, like `sha256`
+    pub algorithm: String,
+    /// The hex encoded value
+    pub value: String,
 }
 
 impl Dependency {
-    pub fn cache_key(&self) -> &str {
-        self.purl.as_str()
+    /// Create a dependency, only knowing its Package URL
+    pub fn new(purl: Url) -> Self {
+        Self {
+            purl,
+            scope: None,
+            direct: None,
+            depth: None,
+            introduced_by: vec![],
+            paths: vec![],
+            origin: None,
+            checksums: vec![],
+            licenses: vec![],
+        }
+    }
+
+    /// The shortest path pulling in the dependency, formatted like `a -> b -> c`.
+    ///
+    /// Returns `None` for direct dependencies, or if the path is unknown.
+    pub fn path(&self) -> Option<String> {
+        match self.paths.first() {
+            Some(path) if path.len() > 1 => Some(
+                path.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" -> "),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The key of the dependency, covering all the information a policy might evaluate.
+    ///
+    /// This includes how the dependency gets pulled in, so a change of the dependency graph
+    /// evaluates it again.
+    pub fn cache_key(&self) -> String {
+        match serde_json::to_string(self) {
+            Ok(key) => key,
+            Err(_) => self.purl.to_string(),
+        }
     }
 }
 
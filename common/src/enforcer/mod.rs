@@ -1,9 +1,11 @@
 mod dependency;
+mod project;
 
 use crate::config::{try_load, Config};
 use crate::enforcer::seedwing::Evaluator;
 use crate::utils::pool::Pool;
 pub use dependency::*;
+pub use project::*;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -0,0 +1,20 @@
+use crate::enforcer::Dependency;
+use seedwing_policy_engine::value::{self, serde::to_value, RuntimeValue};
+use url::Url;
+
+/// The input of the project rule: the project, and all of its dependencies
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Project {
+    /// The Package URLs of the project itself, more than one for a workspace
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<Url>,
+    pub dependencies: Vec<Dependency>,
+}
+
+impl TryFrom<Project> for RuntimeValue {
+    type Error = value::serde::Error;
+
+    fn try_from(value: Project) -> Result<Self, Self::Error> {
+        to_value(&value)
+    }
+}
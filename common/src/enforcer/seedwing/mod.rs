@@ -1,24 +1,30 @@
 //! Seedwing enforcer implementation
 
 pub mod render;
+mod worker;
 
+use self::worker::{Worker, Worlds};
 use crate::{
-    config::{self, Config, Dependencies, FILE_NAME_YAML},
+    config::{self, Config, Exception, ProjectRule, Rule, FILE_NAME_YAML, PROJECT_RULE},
     enforcer::{
-        cache::{Cache, DefaultCache},
-        Dependency,
+        cache::{
+            disk::{self, DiskCache},
+            Cache, DefaultCache, Layered,
+        },
+        Dependency, Project,
     },
     utils::{
         pool::{Pool, PoolError},
-        progress::{NoProgress, Progress, ProgressRunner},
-        span_to_range,
+        progress::{Progress, ProgressRunner},
+        span_to_range, wildcard,
     },
 };
+use anyhow::anyhow;
 use lsp_types::{Diagnostic, DiagnosticSeverity};
 use ropey::Rope;
 use seedwing_policy_engine::{
-    lang::builder::Builder,
-    runtime::{sources::Ephemeral, BuildError, Response, RuntimeError, World},
+    lang::Severity,
+    runtime::{response::Name, BuildError, Response, RuntimeError, SourceLocation},
     value::{self, RuntimeValue},
 };
 use std::{
@@ -26,10 +32,13 @@ use std::{
     fs, io,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 
 const DEFAULT_PACKAGE: &str = "enforcer";
+/// The number of workers, unless configured otherwise
+const DEFAULT_WORKERS: usize = 1;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -43,8 +52,8 @@ pub enum Error {
     Serialization(#[from] value::serde::Error),
     #[error("parse policy error: {0}")]
     ParsePolicy(String, Vec<BuildError>),
-    #[error("build runtime error")]
-    BuildRuntime(Vec<BuildError>),
+    #[error("build runtime error: {0}")]
+    BuildRuntime(String, Vec<BuildError>),
     #[error("runtime error: {0}")]
     Runtime(#[from] RuntimeError),
 }
@@ -59,8 +68,11 @@ impl Evaluator {
         let mut inner = Inner {
             root: root.into(),
             pool,
+            workers: vec![],
             config: None,
             cache: Default::default(),
+            disk_cache: None,
+            build_diagnostics: Default::default(),
         };
         inner.configure().await;
         Self {
@@ -94,6 +106,13 @@ impl Evaluator {
     {
         self.inner.read().await.eval(dependencies, progress).await
     }
+
+    /// Evaluate the project as a whole against the project rule.
+    ///
+    /// Returns `None` if no project rule is configured.
+    pub async fn eval_project(&self, project: Project) -> Result<Option<Response>, Error> {
+        self.inner.read().await.eval_project(project).await
+    }
 }
 
 #[derive(Debug)]
@@ -101,10 +120,17 @@ struct Inner {
     /// Path to the root, containing the `.enforcer` file.
     root: PathBuf,
     pool: Pool,
+    /// The workers, each keeping its own copy of the built policies
+    workers: Vec<Worker>,
 
     config: Option<anyhow::Result<Config>>,
 
     cache: DefaultCache,
+    /// The persistent cache, for the current configuration
+    disk_cache: Option<DiskCache>,
+
+    /// Diagnostics from building the policies of the current configuration
+    build_diagnostics: HashMap<PathBuf, Vec<Diagnostic>>,
 }
 
 impl Inner {
@@ -112,6 +138,38 @@ impl Inner {
     async fn configure(&mut self) {
         self.config = config::try_load(&self.root).await;
         self.cache.invalidate();
+        self.disk_cache = match &self.config {
+            Some(Ok(config)) => persistent_cache(&self.root, config),
+            _ => None,
+        };
+
+        let (dependencies, workers) = match &self.config {
+            Some(Ok(config)) => (config.dependencies.clone(), config.enforcer.workers),
+            _ => (None, None),
+        };
+
+        // each worker builds its own copy of all policies, so only scale up when asked to
+        let workers = workers
+            .unwrap_or(DEFAULT_WORKERS)
+            .clamp(1, self.pool.size().max(1));
+        self.workers
+            .resize_with(workers, || Worker::new(&self.pool));
+
+        // all workers build at the same time, and all of them report the same errors
+        let pending = self
+            .workers
+            .iter()
+            .map(|worker| worker.configure(self.root.clone(), dependencies.clone()))
+            .collect::<Vec<_>>();
+        let mut results = Vec::with_capacity(pending.len());
+        for result in pending {
+            results.push(result.await);
+        }
+
+        self.build_diagnostics = match results.into_iter().find_map(Result::err) {
+            None => Default::default(),
+            Some(err) => self.diag_from_error(err),
+        };
     }
 
     /// Get the configuration
@@ -129,50 +187,28 @@ impl Inner {
 
         // extract config results
 
-        if let Some(Err(err)) = &self.config {
-            // failed to load configuration
-            result.insert(
-                self.root.join(FILE_NAME_YAML),
-                vec![Diagnostic {
-                    message: err.to_string(),
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    ..Default::default()
-                }],
-            );
-        }
-
-        // eval engine results
-        if let Err(err) = self.eval(vec![], NoProgress).await {
-            // as we can't keep the World instance, we also can't prepare and reconfigure it. So
-            // we only know that something is wrong when we run the evaluation. However, we need
-            // to know as soon as the project is loaded.
-            // So we do a dummy run, just to get some details
-            match err {
-                Error::BuildRuntime(errors) => {
-                    if let Some(Ok(Config {
-                        dependencies: Some(dependencies),
-                        enforcer: _,
-                    })) = &self.config
-                    {
-                        let file = self.root.join(&dependencies.policy);
-                        let diags = diag_from_build_errors(&file, errors);
-                        result.insert(file, diags);
-                    }
-                }
-                Error::ParsePolicy(source, errors) => {
-                    let file = self.root.join(source);
-                    let diags = diag_from_build_errors(&file, errors);
-                    result.insert(self.root.join(file), diags);
-                }
-                err => result
-                    .entry(self.root.join(FILE_NAME_YAML))
-                    .or_default()
-                    .push(Diagnostic {
-                        message: format!("Failed to initialize engine: {err}"),
+        match &self.config {
+            Some(Err(err)) => {
+                // failed to load configuration
+                result.insert(
+                    self.root.join(FILE_NAME_YAML),
+                    vec![Diagnostic {
+                        message: err.to_string(),
                         severity: Some(DiagnosticSeverity::ERROR),
                         ..Default::default()
-                    }),
+                    }],
+                );
             }
+            Some(Ok(config)) => result.extend(diag_from_expired_exceptions(config)),
+            None => {}
+        }
+
+        // build results
+        for (file, diags) in &self.build_diagnostics {
+            result
+                .entry(file.clone())
+                .or_default()
+                .extend(diags.iter().cloned());
         }
 
         // return
@@ -180,6 +216,23 @@ impl Inner {
         result
     }
 
+    /// Convert an error from building the policies into diagnostics
+    fn diag_from_error(&self, err: Error) -> HashMap<PathBuf, Vec<Diagnostic>> {
+        match err {
+            Error::BuildRuntime(source, errors) | Error::ParsePolicy(source, errors) => {
+                diag_from_build_errors(&self.root.join(source), errors)
+            }
+            err => HashMap::from([(
+                self.root.join(FILE_NAME_YAML),
+                vec![Diagnostic {
+                    message: format!("Failed to initialize engine: {err}"),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    ..Default::default()
+                }],
+            )]),
+        }
+    }
+
     pub async fn eval<P>(
         &self,
         dependencies: Vec<Dependency>,
@@ -196,16 +249,130 @@ impl Inner {
             _ => return Ok(all_ok(dependencies)),
         };
 
-        let runner = Runner {
-            root: self.root.clone(),
-            config: config.clone(),
-            progress,
-            cache: self.cache.clone(),
+        // report the progress of all workers from a single task
+        let total = dependencies.len();
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let reporter = self.pool.spawn_pinned(move || async move {
+            let progress = progress.start("Scanning dependencies", total).await;
+            while let Some(message) = rx.recv().await {
+                progress.update(Some(message), 1).await;
+            }
+        });
+
+        // split the dependencies into one chunk per worker, keeping the order
+        let chunk_size = ((total + self.workers.len() - 1) / self.workers.len()).max(1);
+        let mut dependencies = dependencies.into_iter();
+        let mut pending = Vec::with_capacity(self.workers.len());
+
+        for worker in &self.workers {
+            let chunk = dependencies.by_ref().take(chunk_size).collect::<Vec<_>>();
+            if chunk.is_empty() {
+                break;
+            }
+
+            let runner = Runner {
+                root: self.root.clone(),
+                config: config.clone(),
+                progress: tx.clone(),
+                cache: Layered(self.cache.clone(), self.disk_cache.clone()),
+            };
+
+            pending
+                .push(worker.run(move |worlds| async move { runner.eval(chunk, &worlds).await }));
+        }
+
+        // only the runners may keep the reporter alive
+        drop(tx);
+
+        let mut outcomes = Vec::with_capacity(total);
+        for result in pending {
+            outcomes.extend(result.await??);
+        }
+
+        reporter.await?;
+
+        Ok(outcomes)
+    }
+
+    async fn eval_project(&self, project: Project) -> Result<Option<Response>, Error> {
+        let Some(Ok(config)) = &self.config else {
+            return Ok(None);
+        };
+        let Some(rule) = config.dependencies.as_ref().and_then(|d| d.project.clone()) else {
+            return Ok(None);
         };
 
-        self.pool
-            .spawn_pinned(move || async move { runner.eval(dependencies).await })
-            .await?
+        // there is only one input, so a single worker is enough
+        let Some(worker) = self.workers.first() else {
+            return Ok(None);
+        };
+
+        let root = self.root.clone();
+        let response = worker
+            .run(move |worlds| async move { eval_project(&root, &rule, project, &worlds).await })
+            .await??;
+
+        Ok(Some(response))
+    }
+}
+
+/// Evaluate the project rule.
+///
+/// The result is not cached, as the input covers all dependencies and changes with any of them.
+async fn eval_project(
+    root: &Path,
+    rule: &ProjectRule,
+    project: Project,
+    worlds: &Worlds,
+) -> Result<Response, Error> {
+    let world = worlds
+        .get(&rule.policy)
+        .ok_or_else(|| Error::Configuration(anyhow!("Policy not built: {}", rule.policy)))?;
+
+    let input: RuntimeValue = project.try_into()?;
+    let evaluation = world
+        .evaluate(
+            requires(root, &rule.policy, &rule.requires),
+            input,
+            Default::default(),
+        )
+        .await?;
+
+    Ok(combine(vec![rule_response(
+        PROJECT_RULE,
+        rule.severity,
+        Response::new(&evaluation),
+    )]))
+}
+
+/// Set up the persistent cache for a configuration, if enabled.
+fn persistent_cache(root: &Path, config: &Config) -> Option<DiskCache> {
+    let settings = &config.enforcer.cache;
+    if !settings.persistent {
+        return None;
+    }
+
+    let dependencies = config.dependencies.as_ref()?;
+    let base = disk::default_location()?;
+    let ttl = settings
+        .ttl
+        .map(Duration::from_secs)
+        .unwrap_or(disk::DEFAULT_TTL);
+
+    if let Err(err) = disk::prune_periodically(
+        &base,
+        ttl,
+        settings.max_size.unwrap_or(disk::DEFAULT_MAX_SIZE),
+    ) {
+        log::info!("Failed to prune cache: {err}");
+    }
+
+    match disk::digest(root, dependencies) {
+        Ok(digest) => Some(DiskCache::new(&base, &digest, ttl)),
+        Err(err) => {
+            log::info!("Unable to compute the digest of the policies: {err}");
+            None
+        }
     }
 }
 
@@ -216,23 +383,20 @@ fn all_ok(dependencies: Vec<Dependency>) -> Vec<(Dependency, Response)> {
         .collect()
 }
 
-struct Runner<P: Progress, C: Cache> {
+struct Runner<C: Cache> {
     root: PathBuf,
     config: Config,
-    progress: P,
+    /// Reports each evaluated dependency
+    progress: mpsc::UnboundedSender<String>,
     cache: C,
 }
 
-impl<P: Progress, C: Cache> Runner<P, C> {
+impl<C: Cache> Runner<C> {
     async fn eval(
         &self,
         dependencies: Vec<Dependency>,
+        worlds: &Worlds,
     ) -> Result<Vec<(Dependency, Response)>, Error> {
-        let progress = self
-            .progress
-            .start("Scanning dependencies", dependencies.len() + 1)
-            .await;
-
         // if we don't have a dependency config
         let dep_config = match &self.config.dependencies {
             Some(dep_config) => dep_config,
@@ -240,60 +404,272 @@ impl<P: Progress, C: Cache> Runner<P, C> {
             None => return Ok(all_ok(dependencies)),
         };
 
-        progress.update(Some("Building world"), None).await;
-
-        let world = self.build_world(dep_config).await?;
+        let requires = dep_config
+            .rules
+            .iter()
+            .map(|rule| requires(&self.root, &rule.policy, &rule.requires))
+            .collect::<Vec<_>>();
 
         let mut outcomes = Vec::with_capacity(dependencies.len());
 
-        let requires = format!("{}::{}", DEFAULT_PACKAGE, dep_config.requires);
-
         for d in dependencies {
-            progress.update(Some(d.purl.clone()), 1).await;
+            let _ = self.progress.send(d.purl.to_string());
 
-            match self.cache.get(&d) {
-                Some(outcome) => outcomes.push((d, outcome.clone())),
+            let response = match self.cache.get(&d) {
+                Some(outcome) => outcome.clone(),
                 None => {
-                    let input: RuntimeValue = d.clone().try_into()?;
-                    let evaluation = world.evaluate(&requires, input, Default::default()).await?;
-                    let response = Response::new(&evaluation);
+                    let mut responses = Vec::with_capacity(dep_config.rules.len());
+                    for (rule, requires) in dep_config.rules.iter().zip(&requires) {
+                        if !applies(rule, &d) {
+                            continue;
+                        }
+                        let input: RuntimeValue = d.clone().try_into()?;
+                        let world = worlds.get(&rule.policy).ok_or_else(|| {
+                            Error::Configuration(anyhow!("Policy not built: {}", rule.policy))
+                        })?;
+                        let evaluation =
+                            world.evaluate(requires, input, Default::default()).await?;
+                        responses.push(rule_response(
+                            &rule.name,
+                            rule.severity,
+                            Response::new(&evaluation),
+                        ));
+                    }
+                    let response = combine(responses);
                     self.cache.store(&d, response.clone());
-                    outcomes.push((d, response));
+                    response
                 }
-            }
+            };
+
+            // waivers are applied after caching, as they might expire
+            let response = waive(&self.config, &d, response);
+            outcomes.push((d, response));
         }
 
         Ok(outcomes)
     }
+}
+
+/// The fully qualified name of the pattern a rule requires.
+///
+/// Patterns of a single policy file are relative to the [`DEFAULT_PACKAGE`], while patterns of
+/// a policy directory must already be fully qualified.
+fn requires(root: &Path, policy: &str, requires: &str) -> String {
+    if root.join(policy).is_dir() {
+        requires.to_string()
+    } else {
+        format!("{}::{}", DEFAULT_PACKAGE, requires)
+    }
+}
+
+/// Check if a rule applies to a dependency.
+fn applies(rule: &Rule, dependency: &Dependency) -> bool {
+    match &rule.filter {
+        Some(filter) => wildcard::matches(filter, dependency.purl.as_str()),
+        None => true,
+    }
+}
+
+impl From<config::Severity> for Severity {
+    fn from(value: config::Severity) -> Self {
+        match value {
+            config::Severity::Advice => Self::Advice,
+            config::Severity::Warning => Self::Warning,
+            config::Severity::Error => Self::Error,
+        }
+    }
+}
+
+/// Wrap the response of a rule, applying the severity override of the rule.
+fn rule_response(name: &str, severity: Option<config::Severity>, response: Response) -> Response {
+    Response {
+        name: Name::Field(name.to_string()),
+        severity: rule_severity(response.severity, severity),
+        reason: response.reason.clone(),
+        rationale: vec![response],
+        ..Default::default()
+    }
+}
+
+/// The severity of a rule, given the severity of its policy and the override of the rule.
+fn rule_severity(severity: Severity, r#override: Option<config::Severity>) -> Severity {
+    match (severity, r#override) {
+        (Severity::None, _) | (_, None) => severity,
+        (_, Some(severity)) => severity.into(),
+    }
+}
 
-    /// Take the configuration and build the world.
-    async fn build_world(&self, dep_config: &Dependencies) -> Result<World, Error> {
-        let mut builder = Builder::new();
+/// Combine the responses of all rules, the overall severity being the highest one.
+fn combine(responses: Vec<Response>) -> Response {
+    let severity = responses
+        .iter()
+        .map(|r| r.severity)
+        .fold(Severity::None, |a, b| if b > a { b } else { a });
+
+    Response {
+        severity,
+        rationale: responses,
+        ..Default::default()
+    }
+}
 
-        let file = self.root.join(&dep_config.policy);
-        log::info!("Loading from: {}", file.display());
+/// Downgrade the failed rules which are waived by an exception to [`Severity::Advice`].
+fn waive(config: &Config, dependency: &Dependency, mut response: Response) -> Response {
+    if config.exceptions.is_empty() {
+        return response;
+    }
 
-        builder
-            .build(Ephemeral::new(DEFAULT_PACKAGE, fs::read_to_string(file)?).iter())
-            .map_err(|err| Error::ParsePolicy(dep_config.policy.clone(), err))?;
+    for rule in &mut response.rationale {
+        if waiver(config, dependency, rule).is_some() {
+            rule.severity = Severity::Advice;
+        }
+    }
 
-        let world = builder.finish().await.map_err(Error::BuildRuntime)?;
+    combine(response.rationale)
+}
 
-        Ok(world)
+/// Find the exception waiving the response of a rule, if the rule failed and was waived.
+///
+/// This checks the severity of the rule before it was downgraded by the exception, so it can be
+/// used on the responses returned by the [`Evaluator`]. Rules which only give advice are never
+/// waived.
+pub fn waiver<'c>(
+    config: &'c Config,
+    dependency: &Dependency,
+    rule: &Response,
+) -> Option<&'c Exception> {
+    let Name::Field(name) = &rule.name else {
+        return None;
+    };
+
+    let r#override = config
+        .dependencies
+        .iter()
+        .flat_map(|dependencies| &dependencies.rules)
+        .find(|r| &r.name == name)
+        .and_then(|r| r.severity);
+    let severity = rule
+        .rationale
+        .first()
+        .map(|policy| rule_severity(policy.severity, r#override))
+        .unwrap_or(rule.severity);
+
+    if severity > Severity::Advice {
+        config.waiver(name, dependency.purl.as_str())
+    } else {
+        None
     }
 }
 
-fn diag_from_build_errors(file: &Path, errors: Vec<BuildError>) -> Vec<Diagnostic> {
-    let file = fs::File::open(file)
-        .ok()
-        .and_then(|f| Rope::from_reader(f).ok());
+/// Create warnings for all exceptions which expired.
+fn diag_from_expired_exceptions(config: &Config) -> HashMap<PathBuf, Vec<Diagnostic>> {
+    let mut result = HashMap::<_, Vec<_>>::new();
+
+    if !config.exceptions.iter().any(|e| e.is_expired()) {
+        return result;
+    }
+
+    // the content of each file, and the position to continue searching from
+    let mut files = HashMap::<&Path, Option<(String, usize)>>::new();
+
+    for exception in &config.exceptions {
+        // exceptions keep the order of their file, so the same purl being listed multiple times
+        // is located by searching after the previous exception
+        let file = files
+            .entry(exception.source.as_path())
+            .or_insert_with(|| {
+                let content = fs::read_to_string(&exception.source).ok()?;
+                let start = content.find("exceptions:").unwrap_or_default();
+                Some((content, start))
+            })
+            .as_mut();
+
+        let span = file.and_then(|(content, cursor)| {
+            let start = *cursor + content[*cursor..].find(&exception.purl)?;
+            *cursor = start + exception.purl.len();
+            Some((&*content, start))
+        });
+
+        if !exception.is_expired() {
+            continue;
+        }
+
+        let range = span
+            .and_then(|(content, start)| {
+                let rope = Rope::from_str(content);
+                let start = rope.try_byte_to_char(start).ok()?;
+                span_to_range(&rope, start..start + exception.purl.chars().count())
+            })
+            .unwrap_or_default();
 
-    errors
+        result
+            .entry(exception.source.clone())
+            .or_default()
+            .push(Diagnostic {
+                range,
+                severity: Some(DiagnosticSeverity::WARNING),
+                message: format!(
+                    "Exception for '{}' expired on {}: {}",
+                    exception.purl,
+                    exception
+                        .expires
+                        .map(|expires| expires.to_string())
+                        .unwrap_or_default(),
+                    exception.reason
+                ),
+                ..Default::default()
+            });
+    }
+
+    result
+}
+
+/// Convert build errors of a policy to diagnostics, grouped by the file they belong to.
+fn diag_from_build_errors(
+    policy: &Path,
+    errors: Vec<BuildError>,
+) -> HashMap<PathBuf, Vec<Diagnostic>> {
+    let mut files = HashMap::<_, Vec<_>>::new();
+    for err in errors {
+        let file = if policy.is_dir() {
+            location_to_file(policy, &source_location(&err).to_string())
+        } else {
+            policy.to_path_buf()
+        };
+        files.entry(file).or_default().push(err);
+    }
+
+    files
         .into_iter()
-        .map(|err| DiagnosticConverter(&file, err).into())
+        .map(|(file, errors)| {
+            let content = fs::File::open(&file)
+                .ok()
+                .and_then(|f| Rope::from_reader(f).ok());
+            let diags = errors
+                .into_iter()
+                .map(|err| DiagnosticConverter(&content, err).into())
+                .collect();
+            (file, diags)
+        })
         .collect()
 }
 
+fn source_location(err: &BuildError) -> &SourceLocation {
+    match err {
+        BuildError::Parser(location, _)
+        | BuildError::ArgumentMismatch(location, _)
+        | BuildError::PatternNotFound(location, _, _) => location,
+    }
+}
+
+/// Map the package name of a policy directory source back to its file.
+fn location_to_file(policy: &Path, location: &str) -> PathBuf {
+    let mut file = policy.to_path_buf();
+    file.extend(location.split("::"));
+    file.set_extension("dog");
+    file
+}
+
 pub struct DiagnosticConverter<'a>(pub &'a Option<Rope>, pub BuildError);
 
 impl<'a> From<DiagnosticConverter<'a>> for Diagnostic {
@@ -0,0 +1,182 @@
+//! A worker, keeping the built worlds around
+
+use super::{Error, DEFAULT_PACKAGE};
+use crate::{
+    config::Dependencies,
+    utils::pool::{Pool, PoolError},
+};
+use seedwing_policy_engine::{
+    data::DirectoryDataSource,
+    lang::builder::Builder,
+    runtime::{
+        sources::{Directory, Ephemeral},
+        World,
+    },
+};
+use std::{
+    collections::HashMap,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    rc::Rc,
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// The worlds, by their policy.
+#[derive(Default)]
+pub struct Worlds(HashMap<String, World>);
+
+impl Worlds {
+    /// Take the configuration and build a world for each policy.
+    async fn build(root: PathBuf, dependencies: Dependencies) -> Result<Self, Error> {
+        let mut worlds = HashMap::new();
+
+        let policies = dependencies
+            .rules
+            .iter()
+            .map(|rule| &rule.policy)
+            .chain(dependencies.project.iter().map(|project| &project.policy));
+
+        for policy in policies {
+            if !worlds.contains_key(policy) {
+                let world = build_world(&root, policy, &dependencies.data).await?;
+                worlds.insert(policy.clone(), world);
+            }
+        }
+
+        Ok(Self(worlds))
+    }
+
+    /// Get the world of a policy.
+    pub fn get(&self, policy: &str) -> Option<&World> {
+        self.0.get(policy)
+    }
+}
+
+/// Build the world of a single policy.
+///
+/// The policy is either a single file, which becomes the [`DEFAULT_PACKAGE`], or a directory
+/// of `.dog` files, with their paths mapping to package names.
+async fn build_world(root: &Path, policy: &str, data: &[String]) -> Result<World, Error> {
+    let mut builder = Builder::new();
+
+    for data in data {
+        builder.data(DirectoryDataSource::new(root.join(data)));
+    }
+
+    let path = root.join(policy);
+    log::info!("Loading from: {}", path.display());
+
+    if path.is_dir() {
+        builder.build(Directory::new(path).iter())
+    } else {
+        builder.build(Ephemeral::new(DEFAULT_PACKAGE, fs::read_to_string(path)?).iter())
+    }
+    .map_err(|err| Error::ParsePolicy(policy.to_string(), err))?;
+
+    let world = builder
+        .finish()
+        .await
+        .map_err(|err| Error::BuildRuntime(policy.to_string(), err))?;
+
+    Ok(world)
+}
+
+type Job = Box<dyn FnOnce(Rc<Worlds>) -> Pin<Box<dyn Future<Output = ()>>> + Send>;
+
+enum Message {
+    Configure {
+        root: PathBuf,
+        dependencies: Option<Dependencies>,
+        reply: oneshot::Sender<Result<(), Error>>,
+    },
+    Run(Job),
+}
+
+/// A task, pinned to a single worker of the pool, which owns the worlds.
+///
+/// As a [`World`] is not `Send`, it can't be moved between threads. So we keep it on one task,
+/// and send the work there instead.
+#[derive(Clone, Debug)]
+pub struct Worker {
+    tx: mpsc::UnboundedSender<Message>,
+}
+
+impl Worker {
+    pub fn new(pool: &Pool) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+
+        pool.spawn_detached(move || async move {
+            let mut worlds = Rc::new(Worlds::default());
+
+            while let Some(message) = rx.recv().await {
+                match message {
+                    Message::Configure {
+                        root,
+                        dependencies,
+                        reply,
+                    } => {
+                        let result = match dependencies {
+                            Some(dependencies) => Worlds::build(root, dependencies).await,
+                            None => Ok(Worlds::default()),
+                        };
+                        let (next, result) = match result {
+                            Ok(next) => (next, Ok(())),
+                            Err(err) => (Worlds::default(), Err(err)),
+                        };
+                        worlds = Rc::new(next);
+                        let _ = reply.send(result);
+                    }
+                    Message::Run(job) => job(worlds.clone()).await,
+                }
+            }
+
+            log::debug!("Worker shut down");
+        });
+
+        Self { tx }
+    }
+
+    /// Re-build the worlds, replacing the current ones.
+    ///
+    /// If building fails, all worlds are dropped. The request is sent right away, so that
+    /// multiple workers can build at the same time.
+    pub fn configure(
+        &self,
+        root: PathBuf,
+        dependencies: Option<Dependencies>,
+    ) -> impl Future<Output = Result<(), Error>> {
+        let (reply, rx) = oneshot::channel();
+        let sent = self.tx.send(Message::Configure {
+            root,
+            dependencies,
+            reply,
+        });
+        async move {
+            sent.map_err(|_| PoolError)?;
+            rx.await.map_err(|_| PoolError)?
+        }
+    }
+
+    /// Run a function on the worker, having access to the current worlds.
+    ///
+    /// The function is sent right away, so that multiple workers can run at the same time.
+    pub fn run<F, Fut, R>(&self, f: F) -> impl Future<Output = Result<R, Error>>
+    where
+        F: FnOnce(Rc<Worlds>) -> Fut + Send + 'static,
+        Fut: Future<Output = R> + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let sent = self.tx.send(Message::Run(Box::new(move |worlds| {
+            Box::pin(async move {
+                let _ = tx.send(f(worlds).await);
+            })
+        })));
+        async move {
+            sent.map_err(|_| PoolError)?;
+            Ok(rx.await.map_err(|_| PoolError)?)
+        }
+    }
+}
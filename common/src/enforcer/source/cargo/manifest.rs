@@ -0,0 +1,138 @@
+//! The parts of a `Cargo.toml` file we need for highlighting, and for reading a project without
+//! running Cargo.
+
+use crate::utils::wildcard;
+use cargo_metadata::DependencyKind;
+use std::collections::BTreeMap;
+use toml::Spanned;
+
+/// Dependencies, by their (spanned) key.
+pub type DependencyTable = BTreeMap<Spanned<String>, toml::Value>;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Manifest {
+    #[serde(default)]
+    pub package: Option<Package>,
+    #[serde(default)]
+    pub dependencies: DependencyTable,
+    #[serde(default)]
+    pub dev_dependencies: DependencyTable,
+    #[serde(default)]
+    pub build_dependencies: DependencyTable,
+    #[serde(default)]
+    pub target: BTreeMap<String, Target>,
+    #[serde(default)]
+    pub workspace: Option<Workspace>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Target {
+    #[serde(default)]
+    pub dependencies: DependencyTable,
+    #[serde(default)]
+    pub dev_dependencies: DependencyTable,
+    #[serde(default)]
+    pub build_dependencies: DependencyTable,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct Package {
+    pub name: String,
+    /// The path of the workspace root, if it isn't the first parent with a workspace
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct Workspace {
+    #[serde(default)]
+    pub dependencies: DependencyTable,
+    /// Paths of the members, which may contain wildcards
+    #[serde(default)]
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl Workspace {
+    /// Check if a package, at a path relative to the workspace root, is a member.
+    pub fn is_member(&self, path: &str) -> bool {
+        let matches = |patterns: &[String]| {
+            patterns.iter().any(|pattern| {
+                wildcard::matches(pattern.trim_start_matches("./").trim_end_matches('/'), path)
+            })
+        };
+        matches(&self.members) && !matches(&self.exclude)
+    }
+}
+
+impl Manifest {
+    /// Find the span of the key declaring the crate `name`.
+    ///
+    /// This takes renamed dependencies (using `package = "name"`) into account.
+    pub fn dependency(&self, name: &str) -> Option<std::ops::Range<usize>> {
+        self.tables()
+            .flat_map(|table| table.iter())
+            .find(|(key, value)| {
+                let package = value
+                    .get("package")
+                    .and_then(|package| package.as_str())
+                    .unwrap_or(key.get_ref());
+                package == name
+            })
+            .map(|(key, _)| key.span())
+    }
+
+    /// The crates this package depends on, with the kind of dependency declaring them.
+    ///
+    /// The dependencies of the workspace are only inherited by its members, and aren't part of
+    /// them.
+    pub fn dependencies(&self) -> impl Iterator<Item = (&str, DependencyKind)> {
+        let tables = [
+            (&self.dependencies, DependencyKind::Normal),
+            (&self.build_dependencies, DependencyKind::Build),
+            (&self.dev_dependencies, DependencyKind::Development),
+        ]
+        .into_iter()
+        .chain(self.target.values().flat_map(|target| {
+            [
+                (&target.dependencies, DependencyKind::Normal),
+                (&target.build_dependencies, DependencyKind::Build),
+                (&target.dev_dependencies, DependencyKind::Development),
+            ]
+        }));
+
+        tables.flat_map(|(table, kind)| {
+            table.iter().map(move |(key, value)| {
+                let package = value
+                    .get("package")
+                    .and_then(|package| package.as_str())
+                    .unwrap_or(key.get_ref());
+                (package, kind)
+            })
+        })
+    }
+
+    fn tables(&self) -> impl Iterator<Item = &DependencyTable> {
+        [
+            &self.dependencies,
+            &self.build_dependencies,
+            &self.dev_dependencies,
+        ]
+        .into_iter()
+        .chain(self.target.values().flat_map(|target| {
+            [
+                &target.dependencies,
+                &target.build_dependencies,
+                &target.dev_dependencies,
+            ]
+        }))
+        .chain(
+            self.workspace
+                .iter()
+                .map(|workspace| &workspace.dependencies),
+        )
+    }
+}
@@ -1,67 +1,529 @@
-use crate::enforcer::{source::Source, Dependency};
-use crate::highlight::Range;
-use crate::utils::projects::CARGO_FILE;
-use anyhow::anyhow;
+use crate::enforcer::{
+    source::{
+        graph::{DependencyGraph, Introductions},
+        Source,
+    },
+    Checksum, Dependency, Origin, Scope,
+};
+use crate::highlight::{Range, TomlHighlighter};
+use crate::utils::{
+    process::{self, Execution},
+    projects::{CARGO_FILE, CARGO_LOCK_FILE},
+};
+use anyhow::{anyhow, bail};
 use async_trait::async_trait;
-use cargo_lock::package::Package;
+use cargo_lock::package::{Package, SourceId};
 use cargo_lock::Lockfile;
-use std::path::PathBuf;
+use cargo_metadata::DependencyKind;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use url::Url;
 
-fn package_to_purl(package: Package) -> Option<Dependency> {
-    let name = package.name;
-    let version = package.version;
+mod manifest;
+
+pub use manifest::Manifest;
+
+fn package_to_dependency(
+    package: Package,
+    introductions: &Introductions<PackageId>,
+    metadata: &Metadata,
+) -> Dependency {
+    let id = PackageId {
+        name: package.name.to_string(),
+        version: package.version.to_string(),
+    };
 
     // the package may have some dependencies, but all the transiant dependencies are flattened
-    // in the cargo lockfile so we skip them.
-    // However it may be useful later to highlight the source dependency when finding an issue.
-    // package.dependencies -> Vec<Dependency>
-    let purl = Url::parse(format!("pkg:cargo/{name}@{version}").as_str()).unwrap();
-
-    // todo : add some more information such as git dependencies, patches, custom registry
-    // let source = package.source;
-    // if let Some(source) = source &&
-    //     let SourceKind::Git(git_ref) = source.kind()
-    //     {
-    //        purl.query_pairs_mut().append_pair("source", format!("{:?}",git_ref).as_str());
-    // }
-
-    Some(Dependency { purl })
+    // in the cargo lockfile so we skip them here. They are tracked by the graph instead.
+    let purl = id.purl();
+    let introduced_by = introductions.introduced_by(&id);
+    let paths = introductions.paths(&id);
+
+    // a transitive dependency inherits the scope of the direct dependencies pulling it in
+    let scope = introduced_by
+        .iter()
+        .filter_map(|direct| metadata.scopes.get(&direct.name))
+        .copied()
+        .min_by_key(|scope| match scope {
+            Scope::Build => 1,
+            Scope::Dev => 2,
+            _ => 0,
+        });
+
+    Dependency {
+        purl,
+        scope,
+        direct: Some(introduced_by.first() == Some(&id)),
+        depth: paths.first().map(Vec::len),
+        introduced_by: introduced_by.iter().map(PackageId::purl).collect(),
+        paths: paths
+            .iter()
+            .map(|path| path.iter().map(PackageId::purl).collect())
+            .collect(),
+        origin: Some(origin(package.source.as_ref())),
+        checksums: package
+            .checksum
+            .map(|checksum| Checksum {
+                algorithm: "sha256".to_string(),
+                value: checksum.to_string(),
+            })
+            .into_iter()
+            .collect(),
+        licenses: metadata.licenses.get(&id).cloned().into_iter().collect(),
+    }
+}
+
+fn origin(source: Option<&SourceId>) -> Origin {
+    match source {
+        // packages without a source are local ones, like workspace members
+        None => Origin::Path { path: None },
+        Some(source) if source.is_git() => Origin::Git {
+            url: source.url().to_string(),
+            revision: source.precise().map(ToString::to_string),
+        },
+        Some(source) if source.is_path() => Origin::Path {
+            path: Some(source.url().path().to_string()),
+        },
+        Some(source) if source.is_default_registry() => Origin::Registry { url: None },
+        Some(source) => Origin::Registry {
+            url: Some(source.url().to_string()),
+        },
+    }
+}
+
+/// Information from `cargo metadata`, which is missing in the lockfile.
+#[derive(Default)]
+struct Metadata {
+    /// The declared licenses
+    licenses: HashMap<PackageId, String>,
+    /// The scope of the direct dependencies, by name
+    scopes: HashMap<String, Scope>,
+}
+
+impl Metadata {
+    fn new(metadata: &cargo_metadata::Metadata, roots: &[&cargo_metadata::Package]) -> Self {
+        let licenses = metadata
+            .packages
+            .iter()
+            .filter_map(|package| {
+                let id = PackageId {
+                    name: package.name.clone(),
+                    version: package.version.to_string(),
+                };
+                Some((id, package.license.clone()?))
+            })
+            .collect();
+
+        let scopes = Self::scopes(
+            roots
+                .iter()
+                .flat_map(|root| &root.dependencies)
+                .map(|dependency| (dependency.name.as_str(), dependency.kind)),
+        );
+
+        Self { licenses, scopes }
+    }
+
+    /// Only the scopes are known from the manifest, the licenses are missing.
+    fn from_manifest(manifest: &Manifest) -> Self {
+        Self {
+            licenses: Default::default(),
+            scopes: Self::scopes(manifest.dependencies()),
+        }
+    }
+
+    fn scopes<'a>(
+        dependencies: impl IntoIterator<Item = (&'a str, DependencyKind)>,
+    ) -> HashMap<String, Scope> {
+        let mut scopes = HashMap::new();
+        for (name, kind) in dependencies {
+            let scope = match kind {
+                DependencyKind::Development => Scope::Dev,
+                DependencyKind::Build => Scope::Build,
+                _ => Scope::Compile,
+            };
+            // a regular dependency wins over a build dependency, which wins over a dev dependency
+            scopes
+                .entry(name.to_string())
+                .and_modify(|current| {
+                    if *current == Scope::Dev || scope == Scope::Compile {
+                        *current = scope;
+                    }
+                })
+                .or_insert(scope);
+        }
+        scopes
+    }
+}
+
+/// Identifies a package in the lockfile
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct PackageId {
+    name: String,
+    version: String,
+}
+
+impl PackageId {
+    fn purl(&self) -> Url {
+        Url::parse(&format!("pkg:cargo/{}@{}", self.name, self.version)).unwrap()
+    }
+
+    fn from_purl(purl: &Url) -> Option<Self> {
+        let (name, version) = purl.path().strip_prefix("cargo/")?.split_once('@')?;
+        Some(Self {
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    }
+}
+
+fn build_graph(packages: &[Package], roots: HashSet<PackageId>) -> DependencyGraph<PackageId> {
+    let mut graph = DependencyGraph::new(roots);
+
+    for package in packages {
+        let dependent = PackageId {
+            name: package.name.to_string(),
+            version: package.version.to_string(),
+        };
+        for dependency in &package.dependencies {
+            graph.add(
+                dependent.clone(),
+                PackageId {
+                    name: dependency.name.to_string(),
+                    version: dependency.version.to_string(),
+                },
+            );
+        }
+    }
+
+    graph
 }
 
 pub struct CargoSource {
     root: PathBuf,
+    /// How to run `cargo metadata`, or `None` to only read the lockfile
+    execution: Option<Execution>,
+    graph: RwLock<DependencyGraph<PackageId>>,
 }
 
 impl CargoSource {
-    pub fn new(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+    pub fn new(root: impl Into<PathBuf>, execution: Execution) -> Self {
+        Self {
+            root: root.into(),
+            execution: Some(execution),
+            graph: Default::default(),
+        }
+    }
+
+    /// A source reading the lockfile without running Cargo, for projects which aren't trusted.
+    ///
+    /// The licenses are unknown, and the lockfile must exist.
+    pub fn from_lockfile(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            execution: None,
+            graph: Default::default(),
+        }
+    }
+
+    async fn load_metadata(
+        &self,
+        execution: &Execution,
+    ) -> anyhow::Result<(Lockfile, HashSet<PackageId>, Metadata)> {
+        // find the project root, as the lockfile is not always along the `Cargo.toml` file.
+        let command = cargo_metadata::MetadataCommand::new()
+            .manifest_path(self.root.join(CARGO_FILE))
+            .cargo_command();
+        let output = process::output(command, execution).await?;
+        let metadata = cargo_metadata::MetadataCommand::parse(String::from_utf8(output)?)?;
+
+        let lockfile_path = metadata.workspace_root.join(CARGO_LOCK_FILE);
+        let lockfile = Lockfile::load(lockfile_path)?;
+
+        // the direct dependencies are the ones of our package, or the whole workspace
+        let roots = match metadata.root_package() {
+            Some(package) => vec![package],
+            None => metadata.workspace_packages(),
+        };
+
+        let info = Metadata::new(&metadata, &roots);
+        let roots = roots
+            .into_iter()
+            .map(|package| PackageId {
+                name: package.name.clone(),
+                version: package.version.to_string(),
+            })
+            .collect();
+
+        Ok((lockfile, roots, info))
+    }
+
+    fn load_lockfile(&self) -> anyhow::Result<(Lockfile, HashSet<PackageId>, Metadata)> {
+        let manifest: Manifest = toml::from_str(&fs::read_to_string(self.root.join(CARGO_FILE))?)?;
+
+        // the lockfile of a workspace member is in the root of the workspace
+        let lockfile_path = self.workspace_root(&manifest).join(CARGO_LOCK_FILE);
+        if !lockfile_path.is_file() {
+            bail!("Missing {CARGO_LOCK_FILE}, which is required unless the project is trusted");
+        }
+        let lockfile = Lockfile::load(lockfile_path)?;
+
+        // the direct dependencies are the ones of our package, or of all local packages for a
+        // virtual manifest, as local packages don't have a source
+        let roots = lockfile
+            .packages
+            .iter()
+            .filter(|package| package.source.is_none())
+            .filter(|package| match &manifest.package {
+                Some(root) => package.name.as_str() == root.name,
+                None => true,
+            })
+            .map(|package| PackageId {
+                name: package.name.to_string(),
+                version: package.version.to_string(),
+            })
+            .collect();
+
+        let info = Metadata::from_manifest(&manifest);
+
+        Ok((lockfile, roots, info))
+    }
+
+    /// Find the root of the workspace the project is a member of, or the project itself.
+    ///
+    /// Like Cargo, this is the first parent with a workspace listing the project as member, unless
+    /// the project points to its workspace.
+    fn workspace_root(&self, manifest: &Manifest) -> PathBuf {
+        if manifest.workspace.is_some() {
+            return self.root.clone();
+        }
+        if let Some(workspace) = manifest
+            .package
+            .as_ref()
+            .and_then(|package| package.workspace.as_ref())
+        {
+            return self.root.join(workspace);
+        }
+
+        let root = self
+            .root
+            .canonicalize()
+            .unwrap_or_else(|_| self.root.clone());
+        root.ancestors()
+            .skip(1)
+            .find(|dir| {
+                let Ok(content) = fs::read_to_string(dir.join(CARGO_FILE)) else {
+                    return false;
+                };
+                let Some(workspace) = toml::from_str::<Manifest>(&content)
+                    .ok()
+                    .and_then(|manifest| manifest.workspace)
+                else {
+                    return false;
+                };
+                let path = root.strip_prefix(dir).unwrap_or(&root);
+                workspace.is_member(&path.to_string_lossy().replace('\\', "/"))
+            })
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.root.clone())
     }
 }
 
 #[async_trait]
 impl Source for CargoSource {
     async fn scan(&self) -> anyhow::Result<Vec<Dependency>> {
-        // find the project root, as the lockfile is not always along the `Cargo.toml` file.
-        let metadata = cargo_metadata::MetadataCommand::new()
-            .manifest_path(&self.root.join(CARGO_FILE))
-            .exec()?;
+        let (lockfile, roots, info) = match &self.execution {
+            Some(execution) => self.load_metadata(execution).await?,
+            None => self.load_lockfile()?,
+        };
 
-        let lockfile_path = metadata.workspace_root.join("Cargo.lock");
-        let lockfile = Lockfile::load(lockfile_path)?;
+        let graph = build_graph(&lockfile.packages, roots);
+        let introductions = graph.introductions();
 
-        Ok(lockfile
+        let dependencies = lockfile
             .packages
             .into_iter()
-            .filter_map(package_to_purl)
-            .collect::<Vec<Dependency>>())
+            .map(|package| package_to_dependency(package, &introductions, &info))
+            .collect::<Vec<Dependency>>();
+
+        *self.graph.write().unwrap() = graph;
+
+        Ok(dependencies)
     }
 
-    fn highlight(&self, _dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
-        Ok((
-            Url::from_file_path(self.root.join(CARGO_FILE))
-                .map_err(|()| anyhow!("Failed to build path URI"))?,
-            Range::default(),
-        ))
+    fn graph(&self) -> DependencyGraph {
+        self.graph.read().unwrap().map(PackageId::purl)
+    }
+
+    fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
+        let path = self.root.join(CARGO_FILE);
+        let content = fs::read_to_string(&path)?;
+        let h = TomlHighlighter::<Manifest>::new(&content)?;
+        let url = Url::from_file_path(&path).map_err(|()| anyhow!("Failed to build path URI"))?;
+
+        // highlight the direct dependency, or the one pulling in the transitive dependency
+        let candidates = match PackageId::from_purl(&dependency.purl) {
+            Some(id) => {
+                let direct = self.graph.read().unwrap().introduced_by(&id);
+                if direct.is_empty() {
+                    vec![id]
+                } else {
+                    direct
+                }
+            }
+            None => vec![],
+        };
+
+        let position = candidates
+            .iter()
+            .find_map(|id| {
+                h.find_with(|manifest| manifest.dependency(&id.name))
+                    .transpose()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok((url, position))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn id(name: &str, version: &str) -> PackageId {
+        PackageId {
+            name: name.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    const LOCKFILE: &str = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["serde", "serde_json"]
+
+[[package]]
+name = "itoa"
+version = "1.0.6"
+
+[[package]]
+name = "serde"
+version = "1.0.160"
+
+[[package]]
+name = "serde_json"
+version = "1.0.96"
+dependencies = ["itoa", "serde"]
+"#;
+
+    #[test]
+    fn test_introduced_by() {
+        let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+
+        let graph = build_graph(&lockfile.packages, HashSet::from([id("app", "0.1.0")]));
+
+        assert_eq!(
+            graph.introduced_by(&id("itoa", "1.0.6")),
+            vec![id("serde_json", "1.0.96")]
+        );
+        assert_eq!(
+            graph.introduced_by(&id("serde", "1.0.160")),
+            vec![id("serde", "1.0.160"), id("serde_json", "1.0.96")]
+        );
+        assert!(graph.introduced_by(&id("app", "0.1.0")).is_empty());
+    }
+
+    #[test]
+    fn test_scope() {
+        let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+        let graph = build_graph(&lockfile.packages, HashSet::from([id("app", "0.1.0")]));
+        let introductions = graph.introductions();
+        let metadata = Metadata {
+            licenses: HashMap::from([(id("itoa", "1.0.6"), "MIT OR Apache-2.0".to_string())]),
+            scopes: HashMap::from([
+                ("serde".to_string(), Scope::Compile),
+                ("serde_json".to_string(), Scope::Dev),
+            ]),
+        };
+
+        let dependencies = lockfile
+            .packages
+            .into_iter()
+            .map(|package| package_to_dependency(package, &introductions, &metadata))
+            .map(|dependency| (dependency.purl.to_string(), dependency))
+            .collect::<HashMap<_, _>>();
+
+        let itoa = &dependencies["pkg:cargo/itoa@1.0.6"];
+        assert_eq!(itoa.scope, Some(Scope::Dev));
+        assert_eq!(itoa.direct, Some(false));
+        assert_eq!(itoa.licenses, vec!["MIT OR Apache-2.0".to_string()]);
+        assert_eq!(itoa.depth, Some(2));
+        assert_eq!(
+            itoa.path().as_deref(),
+            Some("pkg:cargo/serde_json@1.0.96 -> pkg:cargo/itoa@1.0.6")
+        );
+
+        let serde = &dependencies["pkg:cargo/serde@1.0.160"];
+        assert_eq!(serde.scope, Some(Scope::Compile));
+        assert_eq!(serde.direct, Some(true));
+        assert_eq!(serde.origin, Some(Origin::Path { path: None }));
+    }
+
+    #[test]
+    fn test_from_lockfile() {
+        // the lockfile is in the root of the workspace
+        let source = CargoSource::from_lockfile("test-data/cargo/app");
+        let (lockfile, roots, info) = source.load_lockfile().unwrap();
+
+        assert_eq!(lockfile.packages.len(), 5);
+        assert_eq!(roots, HashSet::from([id("app", "0.1.0")]));
+        assert!(info.licenses.is_empty());
+        assert_eq!(
+            info.scopes,
+            HashMap::from([
+                ("lib".to_string(), Scope::Compile),
+                ("serde_json".to_string(), Scope::Dev),
+            ])
+        );
+
+        // not a member of a workspace above, and without a lockfile of its own
+        assert!(CargoSource::from_lockfile("test-data/cargo/standalone")
+            .load_lockfile()
+            .is_err());
+    }
+
+    #[test]
+    fn test_highlight_manifest() {
+        let h = TomlHighlighter::<Manifest>::new(
+            r#"
+[package]
+name = "app"
+
+[dependencies]
+serde = "1"
+json = { package = "serde_json", version = "1" }
+
+[target.'cfg(unix)'.dev-dependencies]
+itoa = "1"
+"#,
+        )
+        .unwrap();
+
+        let find = |name: &str| {
+            h.find_with(|manifest| manifest.dependency(name))
+                .unwrap()
+                .map(|range| (range.start.line, range.start.position, range.end.position))
+        };
+
+        assert_eq!(find("serde"), Some((5, 0, 5)));
+        assert_eq!(find("serde_json"), Some((6, 0, 4)));
+        assert_eq!(find("itoa"), Some((9, 0, 4)));
+        assert_eq!(find("app"), None);
     }
 }
@@ -1,60 +1,205 @@
-use crate::config::{Config, ManifestType};
+use crate::config::{Config, ManifestType, MavenResolver};
 use crate::enforcer::source::cargo::CargoSource;
+use crate::enforcer::source::golang::GoSource;
+use crate::enforcer::source::gradle::{self, GradleSource};
+use crate::enforcer::source::maven::MavenSource;
+use crate::enforcer::source::npm::NpmSource;
+use crate::enforcer::source::python::PythonSource;
+use crate::enforcer::source::sbom::file::FileGenerator;
+use crate::enforcer::source::sbom::gradle::GradleGenerator;
 use crate::enforcer::source::sbom::maven::MavenGenerator;
-use crate::enforcer::source::sbom::SBOM;
+use crate::enforcer::source::sbom::{Generator, OutputCache, SBOM};
 use crate::enforcer::source::Source;
-use crate::utils::projects::{CARGO_FILE, MAVEN_FILE};
+use crate::utils::process::{self, Execution};
+use crate::utils::projects::{
+    is_requirements_file, requirements_files, CARGO_FILE, GO_MOD_FILE, GRADLE_FILE,
+    GRADLE_KTS_FILE, GRADLE_SETTINGS_FILE, GRADLE_SETTINGS_KTS_FILE, MAVEN_FILE, NPM_FILE, PIPFILE,
+    PYPROJECT_FILE,
+};
 use anyhow::{bail, Result};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Files, any of them marking a Gradle project
+const GRADLE_FILES: &[&str] = &[
+    GRADLE_FILE,
+    GRADLE_KTS_FILE,
+    GRADLE_SETTINGS_FILE,
+    GRADLE_SETTINGS_KTS_FILE,
+];
+
+/// Files, any of them marking a Python project, next to `requirements*.txt` files
+const PYTHON_FILES: &[&str] = &[PYPROJECT_FILE, PIPFILE];
 
 pub struct AutoSource {}
 
 impl AutoSource {
+    /// Find the source of a project.
+    ///
+    /// Sources running a build tool do so with the provided execution, using the configured
+    /// timeout. Unless the execution is trusted, only sources reading files of the project are
+    /// available, and finding any other fails with [`process::Error::Untrusted`].
+    ///
+    /// With a cache, the SBOM a build tool generated is reused, until the project's manifests
+    /// change.
     pub async fn find_source(
         path: impl Into<PathBuf>,
         config: Option<Config>,
+        mut execution: Execution,
+        cache: Option<OutputCache>,
     ) -> Result<Box<dyn Source>> {
         let root: PathBuf = path.into();
 
-        if let Some(config) = config {
-            if let Some(source_type) = config.enforcer.source {
-                match source_type {
-                    ManifestType::Cargo => Ok(cargo(root)),
-                    ManifestType::Maven => Ok(maven(root)),
+        let (source_type, sbom, maven_resolver) = match config {
+            Some(config) => {
+                if let Some(timeout) = config.enforcer.generator.timeout {
+                    execution.timeout = Duration::from_secs(timeout);
                 }
-            } else {
-                autodetect(root)
+                (
+                    config.enforcer.source,
+                    config.enforcer.sbom,
+                    config.enforcer.maven.resolver,
+                )
+            }
+            None => (None, None, MavenResolver::default()),
+        };
+
+        let source_type = match source_type {
+            Some(source_type) => source_type,
+            None => match Self::detect(&root) {
+                Some(source_type) => source_type,
+                None => bail!(io::ErrorKind::NotFound),
+            },
+        };
+
+        Ok(match source_type {
+            ManifestType::Cargo => cargo(root, execution),
+            ManifestType::Maven => maven(root, maven_resolver, execution, cache)?,
+            ManifestType::Npm => npm(root),
+            ManifestType::Python => python(root),
+            ManifestType::Go => go(root),
+            ManifestType::Gradle => gradle(root, execution, cache)?,
+            ManifestType::Sbom => match sbom {
+                Some(path) => Box::new(SBOM::new(FileGenerator::new(path))),
+                None => {
+                    bail!("The 'Sbom' source requires the path of the SBOM, using 'enforcer.sbom'")
+                }
+            },
+        })
+    }
+
+    /// Detect the type of project, either from a directory or a manifest file.
+    pub fn detect(path: &Path) -> Option<ManifestType> {
+        if path.is_dir() {
+            if path.join(CARGO_FILE).exists() {
+                return Some(ManifestType::Cargo);
+            }
+
+            if path.join(MAVEN_FILE).exists() {
+                return Some(ManifestType::Maven);
+            }
+
+            if GRADLE_FILES.iter().any(|file| path.join(file).exists()) {
+                return Some(ManifestType::Gradle);
+            }
+
+            if path.join(NPM_FILE).exists() {
+                return Some(ManifestType::Npm);
             }
-        } else {
-            autodetect(root)
+
+            if PYTHON_FILES.iter().any(|file| path.join(file).exists())
+                || !requirements_files(path).unwrap_or_default().is_empty()
+            {
+                return Some(ManifestType::Python);
+            }
+
+            if path.join(GO_MOD_FILE).exists() {
+                return Some(ManifestType::Go);
+            }
+        } else if path.ends_with(CARGO_FILE) {
+            return Some(ManifestType::Cargo);
+        } else if path.ends_with(MAVEN_FILE) {
+            return Some(ManifestType::Maven);
+        } else if GRADLE_FILES.iter().any(|file| path.ends_with(file)) {
+            return Some(ManifestType::Gradle);
+        } else if path.ends_with(NPM_FILE) {
+            return Some(ManifestType::Npm);
+        } else if PYTHON_FILES.iter().any(|file| path.ends_with(file))
+            || path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(is_requirements_file)
+                .unwrap_or_default()
+        {
+            return Some(ManifestType::Python);
+        } else if path.ends_with(GO_MOD_FILE) {
+            return Some(ManifestType::Go);
         }
+
+        None
     }
 }
 
-fn autodetect(path: PathBuf) -> Result<Box<dyn Source>> {
-    if path.is_dir() {
-        let cargo_path = path.join(CARGO_FILE);
-        if cargo_path.exists() {
-            return Ok(cargo(path));
+fn maven(
+    root: impl Into<PathBuf>,
+    resolver: MavenResolver,
+    execution: Execution,
+    cache: Option<OutputCache>,
+) -> Result<Box<dyn Source>, process::Error> {
+    Ok(match resolver {
+        MavenResolver::Native => Box::new(MavenSource::new(root)),
+        MavenResolver::Plugin => {
+            execution.check("mvn")?;
+            generated(MavenGenerator::new(root, execution), cache)
         }
+    })
+}
 
-        let maven_path = path.join(MAVEN_FILE);
-        if maven_path.exists() {
-            return Ok(maven(path));
-        }
-    } else if path.ends_with(CARGO_FILE) {
-        return Ok(cargo(path));
-    } else if path.ends_with(MAVEN_FILE) {
-        return Ok(maven(path));
+/// Read the lockfiles if the project uses dependency locking, or run the build otherwise
+fn gradle(
+    root: impl Into<PathBuf>,
+    execution: Execution,
+    cache: Option<OutputCache>,
+) -> Result<Box<dyn Source>, process::Error> {
+    let root = root.into();
+    Ok(if gradle::is_locked(&root) {
+        Box::new(GradleSource::new(root))
+    } else {
+        execution.check("gradle")?;
+        generated(GradleGenerator::new(root, execution), cache)
+    })
+}
+
+/// An SBOM generated by a build tool, reused if there is a cache
+fn generated<G>(generator: G, cache: Option<OutputCache>) -> Box<dyn Source>
+where
+    G: Generator + Send + Sync + 'static,
+{
+    let sbom = SBOM::new(generator);
+    match cache {
+        Some(cache) => Box::new(sbom.with_cache(cache)),
+        None => Box::new(sbom),
     }
-    bail!(io::ErrorKind::NotFound)
 }
 
-fn maven(root: impl Into<PathBuf>) -> Box<dyn Source> {
-    Box::new(SBOM::new(MavenGenerator::new(root)))
+/// Cargo is run for the metadata of the project, which runs the `rustc` the project might
+/// configure in its `.cargo/config.toml`. Untrusted projects only have their lockfile read.
+fn cargo(root: impl Into<PathBuf>, execution: Execution) -> Box<dyn Source> {
+    match execution.check("cargo") {
+        Ok(()) => Box::new(CargoSource::new(root, execution)),
+        Err(_) => Box::new(CargoSource::from_lockfile(root)),
+    }
+}
+
+fn npm(root: impl Into<PathBuf>) -> Box<dyn Source> {
+    Box::new(NpmSource::new(root))
+}
+
+fn python(root: impl Into<PathBuf>) -> Box<dyn Source> {
+    Box::new(PythonSource::new(root))
 }
 
-fn cargo(root: impl Into<PathBuf>) -> Box<dyn Source> {
-    Box::new(CargoSource::new(root))
+fn go(root: impl Into<PathBuf>) -> Box<dyn Source> {
+    Box::new(GoSource::new(root))
 }
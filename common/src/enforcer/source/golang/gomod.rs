@@ -0,0 +1,206 @@
+//! The `go.mod` file of a Go module.
+//!
+//! Directives are either on a single line (`require example.com/a v1.0.0`), or in a block
+//! (`require ( ... )`). Requirements which are only needed by other modules are marked using an
+//! `// indirect` comment.
+
+use anyhow::bail;
+use std::ops::Range;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GoMod {
+    /// The path of the module itself
+    pub module: Option<String>,
+    pub require: Vec<Require>,
+    pub replace: Vec<Replace>,
+    /// Module versions which must not be used
+    pub exclude: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Require {
+    pub path: String,
+    pub version: String,
+    pub indirect: bool,
+    /// The span of the line
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replace {
+    pub path: String,
+    /// Only replace this version, all versions if missing
+    pub version: Option<String>,
+    pub target: Target,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Another module
+    Module { path: String, version: String },
+    /// A local directory
+    Path(String),
+}
+
+impl GoMod {
+    /// Find the replacement of a module version.
+    pub fn replacement(&self, path: &str, version: &str) -> Option<&Target> {
+        // a replacement of a specific version takes precedence
+        self.replace
+            .iter()
+            .filter(|replace| replace.path == path)
+            .find(|replace| replace.version.as_deref() == Some(version))
+            .or_else(|| {
+                self.replace
+                    .iter()
+                    .find(|replace| replace.path == path && replace.version.is_none())
+            })
+            .map(|replace| &replace.target)
+    }
+
+    pub fn is_excluded(&self, path: &str, version: &str) -> bool {
+        self.exclude.iter().any(|(p, v)| p == path && v == version)
+    }
+
+    /// Find the span of the requirement of a module.
+    pub fn find(&self, path: &str) -> Option<Range<usize>> {
+        self.require
+            .iter()
+            .find(|require| require.path == path)
+            .map(|require| require.span.clone())
+    }
+}
+
+fn unquote(token: &str) -> String {
+    token.trim_matches(|c| c == '"' || c == '`').to_string()
+}
+
+pub fn parse(content: &str) -> anyhow::Result<GoMod> {
+    let mut result = GoMod::default();
+    // the directive of the current block
+    let mut block: Option<String> = None;
+    let mut offset = 0;
+
+    for (number, line) in content.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+
+        let (code, comment) = match line.split_once("//") {
+            Some((code, comment)) => (code, Some(comment.trim())),
+            None => (line, None),
+        };
+        let tokens = code.split_whitespace().collect::<Vec<_>>();
+
+        let (directive, args) = match (&block, tokens.as_slice()) {
+            (_, []) => continue,
+            (Some(_), [")"]) => {
+                block = None;
+                continue;
+            }
+            (Some(directive), args) => (directive.as_str(), args),
+            (None, [directive, "("]) => {
+                block = Some(directive.to_string());
+                continue;
+            }
+            (None, [directive, args @ ..]) => (*directive, args),
+        };
+
+        let trimmed = line.trim();
+        let begin = start + (line.len() - line.trim_start().len());
+        let span = begin..begin + trimmed.len();
+
+        match (directive, args) {
+            ("module", [path]) => result.module = Some(unquote(path)),
+            ("require", [path, version]) => result.require.push(Require {
+                path: unquote(path),
+                version: unquote(version),
+                indirect: comment
+                    .map(|comment| comment == "indirect" || comment.starts_with("indirect;"))
+                    .unwrap_or_default(),
+                span,
+            }),
+            ("exclude", [path, version]) => {
+                result.exclude.push((unquote(path), unquote(version)));
+            }
+            ("replace", args) => {
+                let (path, version, target) = match args {
+                    [path, "=>", target @ ..] => (path, None, target),
+                    [path, version, "=>", target @ ..] => (path, Some(unquote(version)), target),
+                    _ => bail!("Invalid replace directive, line {}: {trimmed}", number + 1),
+                };
+                let target = match target {
+                    [path, version] => Target::Module {
+                        path: unquote(path),
+                        version: unquote(version),
+                    },
+                    [path] => Target::Path(unquote(path)),
+                    _ => bail!("Invalid replace directive, line {}: {trimmed}", number + 1),
+                };
+                result.replace.push(Replace {
+                    path: unquote(path),
+                    version,
+                    target,
+                });
+            }
+            ("module" | "require" | "exclude", _) => {
+                bail!(
+                    "Invalid {directive} directive, line {}: {trimmed}",
+                    number + 1
+                )
+            }
+            // go, toolchain, retract, godebug, ...
+            _ => {}
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content = r#"module example.com/app
+
+go 1.21
+
+require github.com/pkg/errors v0.9.1
+
+require (
+	golang.org/x/text v0.14.0 // indirect
+	rsc.io/quote v1.5.2
+)
+
+replace (
+	rsc.io/quote v1.5.2 => example.com/fork/quote v1.5.3
+	golang.org/x/text => ../text
+)
+
+exclude golang.org/x/net v1.2.3
+"#;
+        let gomod = parse(content).unwrap();
+
+        assert_eq!(gomod.module.as_deref(), Some("example.com/app"));
+        assert_eq!(gomod.require.len(), 3);
+        assert!(!gomod.require[0].indirect);
+        assert!(gomod.require[1].indirect);
+        assert_eq!(
+            &content[gomod.find("rsc.io/quote").unwrap()],
+            "rsc.io/quote v1.5.2"
+        );
+        assert_eq!(
+            gomod.replacement("rsc.io/quote", "v1.5.2"),
+            Some(&Target::Module {
+                path: "example.com/fork/quote".to_string(),
+                version: "v1.5.3".to_string()
+            })
+        );
+        assert_eq!(
+            gomod.replacement("golang.org/x/text", "v0.14.0"),
+            Some(&Target::Path("../text".to_string()))
+        );
+        assert!(gomod.is_excluded("golang.org/x/net", "v1.2.3"));
+    }
+}
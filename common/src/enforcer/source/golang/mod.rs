@@ -0,0 +1,192 @@
+//! Dependencies of Go modules, read from `go.mod` and `go.sum`.
+//!
+//! Since Go 1.17, `go.mod` lists all modules needed to build the packages of the main module,
+//! marking those which are not imported by it as indirect. It doesn't record which module pulls
+//! in an indirect one, so only the direct dependencies are part of the graph.
+
+use crate::enforcer::{
+    source::{graph::DependencyGraph, Source},
+    Checksum, Dependency, Origin,
+};
+use crate::highlight::{Range, TextHighlighter};
+use crate::utils::projects::{GO_MOD_FILE, GO_SUM_FILE};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use url::Url;
+
+mod gomod;
+
+pub use gomod::{GoMod, Require, Target};
+
+fn purl(path: &str, version: Option<&str>) -> anyhow::Result<Url> {
+    Ok(Url::parse(&match version {
+        Some(version) => format!("pkg:golang/{path}@{version}"),
+        None => format!("pkg:golang/{path}"),
+    })?)
+}
+
+/// Get the module path of a Go Package URL.
+fn path_from_purl(purl: &Url) -> Option<&str> {
+    let (path, _) = purl.path().strip_prefix("golang/")?.rsplit_once('@')?;
+    Some(path)
+}
+
+/// Parse a `go.sum` file, into the checksums of the modules, by their path and version.
+///
+/// Go hashes the files of a module (`h1:<base64>`, a SHA-256 over the list of file hashes). The
+/// hashes of the `go.mod` files alone are skipped.
+fn parse_sum(content: &str) -> HashMap<(&str, &str), Vec<Checksum>> {
+    let mut result = HashMap::<_, Vec<_>>::new();
+
+    for line in content.lines() {
+        let [path, version, hash] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+            continue;
+        };
+        if version.ends_with("/go.mod") {
+            continue;
+        }
+        let Some((algorithm, value)) = hash.split_once(':') else {
+            continue;
+        };
+        let Ok(value) = STANDARD.decode(value) else {
+            continue;
+        };
+
+        result.entry((path, version)).or_default().push(Checksum {
+            algorithm: algorithm.to_string(),
+            value: hex::encode(value),
+        });
+    }
+
+    result
+}
+
+pub struct GoSource {
+    root: PathBuf,
+    graph: RwLock<DependencyGraph>,
+}
+
+impl GoSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            graph: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Source for GoSource {
+    async fn scan(&self) -> anyhow::Result<Vec<Dependency>> {
+        let gomod = gomod::parse(&fs::read_to_string(self.root.join(GO_MOD_FILE))?)?;
+
+        // missing if there are no dependencies
+        let sum = match fs::read_to_string(self.root.join(GO_SUM_FILE)) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            result => result?,
+        };
+        let sums = parse_sum(&sum);
+
+        let root = purl(gomod.module.as_deref().unwrap_or("main"), None)?;
+        let mut graph = DependencyGraph::new(HashSet::from([root.clone()]));
+        let mut dependencies = vec![];
+
+        for require in &gomod.require {
+            if gomod.is_excluded(&require.path, &require.version) {
+                log::warn!(
+                    "Skipping excluded module: {}@{}",
+                    require.path,
+                    require.version
+                );
+                continue;
+            }
+
+            let module = purl(&require.path, Some(&require.version))?;
+            if !require.indirect {
+                graph.add(root.clone(), module.clone());
+            }
+
+            let mut dependency = Dependency::new(module);
+
+            // go.sum records the module actually used
+            let (origin, sum) = match gomod.replacement(&require.path, &require.version) {
+                Some(Target::Module { path, version }) => (
+                    Origin::Replaced {
+                        purl: purl(path, Some(version))?,
+                    },
+                    sums.get(&(path.as_str(), version.as_str())),
+                ),
+                Some(Target::Path(path)) => (
+                    Origin::Path {
+                        path: Some(path.clone()),
+                    },
+                    None,
+                ),
+                None => (
+                    Origin::Registry { url: None },
+                    sums.get(&(require.path.as_str(), require.version.as_str())),
+                ),
+            };
+            dependency.origin = Some(origin);
+            dependency.checksums = sum.cloned().unwrap_or_default();
+
+            dependencies.push(dependency);
+        }
+
+        let introductions = graph.introductions();
+        for dependency in &mut dependencies {
+            introductions.annotate(dependency);
+        }
+
+        *self.graph.write().unwrap() = graph;
+
+        Ok(dependencies)
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        self.graph.read().unwrap().clone()
+    }
+
+    fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
+        let path = self.root.join(GO_MOD_FILE);
+        let content = fs::read_to_string(&path)?;
+        let gomod = gomod::parse(&content)?;
+        let h = TextHighlighter::new(&content);
+        let url = Url::from_file_path(&path).map_err(|()| anyhow!("Failed to build path URI"))?;
+
+        let position = match path_from_purl(&dependency.purl) {
+            Some(module) => h.find_with(|_| gomod.find(module))?.unwrap_or_default(),
+            None => Range::default(),
+        };
+
+        Ok((url, position))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_sum() {
+        let sums = parse_sum(
+            r#"github.com/pkg/errors v0.9.1 h1:FEBLx1zS214owpjy7qsBeixbURkuhQAwrK5UwLGTwt4=
+github.com/pkg/errors v0.9.1/go.mod h1:bwawxfHBFNV+L2hUp1rHADufV3IMtnDRdf1r5NINEl0=
+"#,
+        );
+
+        assert_eq!(sums.len(), 1);
+        let checksums = &sums[&("github.com/pkg/errors", "v0.9.1")];
+        assert_eq!(checksums[0].algorithm, "h1");
+        assert_eq!(
+            checksums[0].value,
+            "14404bc75cd2db5e28c298f2eeab017a2c5b51192e850030acae54c0b193c2de"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Locating dependencies in Gradle build files, and version catalogs
+//!
+//! Build files are scripts (Groovy or Kotlin), which we don't evaluate. Instead, we look for the
+//! usual notations of a dependency, on a single line:
+//!
+//! * `implementation("group:artifact:version")`, or a `module = "group:artifact"` of a catalog
+//! * `implementation group: 'group', name: 'artifact'`, or `group = "group", name = "artifact"`
+
+use std::ops::Range;
+
+/// Iterate over the lines of a content, with the span of their trimmed text.
+fn lines(content: &str) -> impl Iterator<Item = (&str, Range<usize>)> {
+    let mut offset = 0;
+    content.split_inclusive('\n').map(move |line| {
+        let start = offset + (line.len() - line.trim_start().len());
+        offset += line.len();
+        let trimmed = line.trim();
+        (trimmed, start..start + trimmed.len())
+    })
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with("//") || line.starts_with('#') || line.starts_with('*')
+}
+
+/// Check if the line contains the coordinates, not being the prefix of other coordinates.
+fn contains_coordinates(line: &str, coordinates: &str) -> bool {
+    line.match_indices(coordinates).any(|(index, _)| {
+        let before = line[..index].chars().next_back();
+        let after = line[index + coordinates.len()..].chars().next();
+        matches!(before, Some('"' | '\'')) && matches!(after, Some('"' | '\'' | ':' | '@'))
+    })
+}
+
+fn contains_quoted(line: &str, value: &str) -> bool {
+    line.contains(&format!("\"{value}\"")) || line.contains(&format!("'{value}'"))
+}
+
+/// Find the span of the line declaring a dependency.
+pub fn find_dependency(content: &str, group: &str, artifact: &str) -> Option<Range<usize>> {
+    let coordinates = format!("{group}:{artifact}");
+
+    lines(content)
+        .filter(|(line, _)| !is_comment(line))
+        .find(|(line, _)| {
+            contains_coordinates(line, &coordinates)
+                || (contains_quoted(line, group) && contains_quoted(line, artifact))
+        })
+        .map(|(_, span)| span)
+}
+
+/// Find the span of the line opening the `dependencies` block.
+pub fn find_dependencies_block(content: &str) -> Option<Range<usize>> {
+    lines(content)
+        .find(|(line, _)| {
+            line.strip_prefix("dependencies")
+                .map(|rest| rest.trim_start().starts_with('{'))
+                .unwrap_or_default()
+        })
+        .map(|(_, span)| span)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_dependency() {
+        let content = r#"plugins {
+    id 'java'
+}
+
+dependencies {
+    // implementation 'com.google.guava:guava:30.0-jre'
+    implementation 'com.google.guava:guava-testlib:31.1-jre'
+    implementation 'com.google.guava:guava:31.1-jre'
+    testImplementation group: 'junit', name: 'junit', version: '4.13.2'
+}
+"#;
+
+        let span = find_dependency(content, "com.google.guava", "guava").unwrap();
+        assert_eq!(
+            &content[span],
+            "implementation 'com.google.guava:guava:31.1-jre'"
+        );
+        let span = find_dependency(content, "junit", "junit").unwrap();
+        assert!(content[span].starts_with("testImplementation"));
+        let span = find_dependencies_block(content).unwrap();
+        assert_eq!(&content[span], "dependencies {");
+    }
+}
@@ -0,0 +1,214 @@
+//! Dependencies of Gradle projects, read from the lockfiles of Gradle's dependency locking.
+//!
+//! Projects without lockfiles are handled by running the CycloneDX Gradle plugin, see
+//! [`GradleGenerator`](crate::enforcer::source::sbom::gradle::GradleGenerator).
+
+use crate::enforcer::{
+    source::{graph::DependencyGraph, maven::MavenDependency, Source},
+    Dependency, Scope,
+};
+use crate::highlight::{Range, TextHighlighter};
+use crate::utils::projects::{
+    GRADLE_BUILDSCRIPT_LOCK_FILE, GRADLE_FILE, GRADLE_KTS_FILE, GRADLE_LOCK_FILE,
+    GRADLE_VERSION_CATALOG,
+};
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+pub mod highlight;
+
+/// Check if a project uses dependency locking.
+pub fn is_locked(root: &Path) -> bool {
+    root.join(GRADLE_LOCK_FILE).exists() || root.join(GRADLE_BUILDSCRIPT_LOCK_FILE).exists()
+}
+
+/// The build file of the project, either the Groovy or the Kotlin variant.
+fn build_file(root: &Path) -> PathBuf {
+    let kts = root.join(GRADLE_KTS_FILE);
+    if kts.exists() {
+        kts
+    } else {
+        root.join(GRADLE_FILE)
+    }
+}
+
+/// Map the configurations locking a dependency to a scope.
+///
+/// Configurations may be prefixed, like `debugRuntimeClasspath` of Android builds.
+fn scope(configurations: &[&str]) -> Option<Scope> {
+    let is_test = |c: &str| c.starts_with("test") || c.contains("Test");
+    let is_main = |suffix: &str| {
+        configurations
+            .iter()
+            .any(|c| !is_test(c) && c.to_lowercase().ends_with(suffix))
+    };
+
+    match (is_main("compileclasspath"), is_main("runtimeclasspath")) {
+        (true, true) => Some(Scope::Compile),
+        (false, true) => Some(Scope::Runtime),
+        (true, false) => Some(Scope::Provided),
+        _ if configurations.iter().any(|c| is_test(c)) => Some(Scope::Test),
+        // like `annotationProcessor`
+        _ if !configurations.is_empty() => Some(Scope::Build),
+        _ => None,
+    }
+}
+
+/// Parse a lockfile, with lines like `group:artifact:version=configuration,...`.
+fn parse_lockfile(content: &str, buildscript: bool) -> anyhow::Result<Vec<Dependency>> {
+    let mut dependencies = vec![];
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (coordinates, configurations) = line.split_once('=').unwrap_or((line, ""));
+        // configurations without any dependencies
+        if coordinates == "empty" {
+            continue;
+        }
+
+        let [group, artifact, version] = coordinates.split(':').collect::<Vec<_>>()[..] else {
+            bail!("Invalid lockfile entry: {line}");
+        };
+
+        let mut dependency = Dependency::new(Url::parse(&format!(
+            "pkg:maven/{group}/{artifact}@{version}"
+        ))?);
+        dependency.scope = if buildscript {
+            Some(Scope::Build)
+        } else {
+            let configurations = configurations
+                .split(',')
+                .filter(|c| !c.is_empty())
+                .collect::<Vec<_>>();
+            scope(&configurations)
+        };
+
+        dependencies.push(dependency);
+    }
+
+    Ok(dependencies)
+}
+
+/// Find the range to highlight, in the build file or the version catalog.
+///
+/// Candidates are tried in order, falling back to the `dependencies` block of the build file.
+pub fn highlight(root: &Path, candidates: &[Dependency]) -> anyhow::Result<(Url, Range)> {
+    let build_file = build_file(root);
+    let files = [build_file.clone(), root.join(GRADLE_VERSION_CATALOG)];
+
+    let contents = files
+        .iter()
+        .filter_map(|path| Some((path, fs::read_to_string(path).ok()?)))
+        .collect::<Vec<_>>();
+
+    for dependency in candidates
+        .iter()
+        .filter_map(|d| MavenDependency::from_purl(&d.purl))
+    {
+        for (path, content) in &contents {
+            let h = TextHighlighter::new(content);
+            if let Some(range) = h.find_with(|content| {
+                highlight::find_dependency(content, &dependency.group_id, &dependency.artifact_id)
+            })? {
+                let url =
+                    Url::from_file_path(path).map_err(|()| anyhow!("Failed to build path URI"))?;
+                return Ok((url, range));
+            }
+        }
+    }
+
+    let url = Url::from_file_path(&build_file).map_err(|()| anyhow!("Failed to build path URI"))?;
+    let range = match fs::read_to_string(&build_file) {
+        Ok(content) => {
+            let h = TextHighlighter::new(&content);
+            h.find_with(highlight::find_dependencies_block)?
+                .unwrap_or_else(|| h.full_range())
+        }
+        Err(_) => Range::default(),
+    };
+
+    Ok((url, range))
+}
+
+/// A Gradle project, using dependency locking.
+pub struct GradleSource {
+    root: PathBuf,
+}
+
+impl GradleSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Source for GradleSource {
+    async fn scan(&self) -> anyhow::Result<Vec<Dependency>> {
+        let mut seen = HashSet::new();
+        let mut dependencies = vec![];
+
+        for (file, buildscript) in [
+            (GRADLE_LOCK_FILE, false),
+            (GRADLE_BUILDSCRIPT_LOCK_FILE, true),
+        ] {
+            let content = match fs::read_to_string(self.root.join(file)) {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                result => result?,
+            };
+            for dependency in parse_lockfile(&content, buildscript)? {
+                if seen.insert(dependency.purl.clone()) {
+                    dependencies.push(dependency);
+                }
+            }
+        }
+
+        Ok(dependencies)
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        // lockfiles only list the resolved dependencies
+        Default::default()
+    }
+
+    fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
+        highlight(&self.root, std::slice::from_ref(dependency))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_lockfile() {
+        let dependencies = parse_lockfile(
+            r#"# This is a Gradle generated file for dependency locking.
+# Manual edits can break the build and are not advised.
+# This file is expected to be part of source control.
+com.google.guava:guava:31.1-jre=compileClasspath,runtimeClasspath
+junit:junit:4.13.2=testCompileClasspath,testRuntimeClasspath
+org.slf4j:slf4j-simple:2.0.7=runtimeClasspath
+empty=annotationProcessor
+"#,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(dependencies.len(), 3);
+        assert_eq!(
+            dependencies[0].purl.as_str(),
+            "pkg:maven/com.google.guava/guava@31.1-jre"
+        );
+        assert_eq!(dependencies[0].scope, Some(Scope::Compile));
+        assert_eq!(dependencies[1].scope, Some(Scope::Test));
+        assert_eq!(dependencies[2].scope, Some(Scope::Runtime));
+    }
+}
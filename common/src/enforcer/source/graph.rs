@@ -0,0 +1,432 @@
+use crate::enforcer::Dependency;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use url::Url;
+
+/// A dependency graph, by default of Package URLs.
+///
+/// The graph is stored in reverse, mapping packages to the packages depending on them.
+#[derive(Clone, Debug)]
+pub struct DependencyGraph<T = Url> {
+    /// The packages declaring the direct dependencies
+    roots: HashSet<T>,
+    /// Packages, and the packages depending on them
+    dependents: HashMap<T, Vec<T>>,
+}
+
+impl<T> Default for DependencyGraph<T> {
+    fn default() -> Self {
+        Self {
+            roots: Default::default(),
+            dependents: Default::default(),
+        }
+    }
+}
+
+impl<T> DependencyGraph<T>
+where
+    T: Clone + Eq + Hash,
+{
+    pub fn new(roots: HashSet<T>) -> Self {
+        Self {
+            roots,
+            dependents: Default::default(),
+        }
+    }
+
+    /// Record that `dependent` depends on `dependency`.
+    pub fn add(&mut self, dependent: T, dependency: T) {
+        self.dependents
+            .entry(dependency)
+            .or_default()
+            .push(dependent);
+    }
+
+    /// Add a package of the project itself, like a module.
+    pub fn add_root(&mut self, root: T) {
+        self.roots.insert(root);
+    }
+
+    /// The packages of the project itself.
+    pub fn roots(&self) -> impl Iterator<Item = &T> {
+        self.roots.iter()
+    }
+
+    /// All edges, from the dependent to the dependency.
+    pub fn edges(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.dependents.iter().flat_map(|(dependency, dependents)| {
+            dependents
+                .iter()
+                .map(move |dependent| (dependent, dependency))
+        })
+    }
+
+    /// Convert the graph into one using a different identifier.
+    pub fn map<U, F>(&self, f: F) -> DependencyGraph<U>
+    where
+        U: Clone + Eq + Hash,
+        F: Fn(&T) -> U,
+    {
+        let mut result = DependencyGraph::new(self.roots.iter().map(&f).collect());
+        for (dependent, dependency) in self.edges() {
+            result.add(f(dependent), f(dependency));
+        }
+        result
+    }
+
+    /// Check if a package is a direct dependency.
+    pub fn is_direct(&self, id: &T) -> bool {
+        self.dependents
+            .get(id)
+            .map(|dependents| dependents.iter().any(|d| self.roots.contains(d)))
+            .unwrap_or_default()
+    }
+
+    /// Find the direct dependencies which pull in a package, the nearest ones first.
+    ///
+    /// If the package is a direct dependency itself, it will be part of the result.
+    pub fn introduced_by(&self, id: &T) -> Vec<T> {
+        self.paths(id)
+            .into_iter()
+            .filter_map(|path| path.into_iter().next())
+            .collect()
+    }
+
+    /// Find the roots declaring the direct dependencies which pull in a package.
+    pub fn roots_of(&self, id: &T) -> HashSet<T> {
+        self.introduced_by(id)
+            .iter()
+            .filter_map(|direct| self.dependents.get(direct))
+            .flatten()
+            .filter(|dependent| self.roots.contains(*dependent))
+            .cloned()
+            .collect()
+    }
+
+    /// Find the direct dependencies of a single root which pull in a package, the nearest ones
+    /// first.
+    pub fn introduced_by_root(&self, root: &T, id: &T) -> Vec<T> {
+        self.introduced_by(id)
+            .into_iter()
+            .filter(|direct| {
+                self.dependents
+                    .get(direct)
+                    .map(|dependents| dependents.contains(root))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Find the shortest path from each direct dependency pulling in a package, the shortest
+    /// ones first.
+    ///
+    /// Each path starts with the direct dependency, and ends with the package itself.
+    pub fn paths(&self, id: &T) -> Vec<Vec<T>> {
+        let mut result = vec![];
+        // the next package on the way to `id`
+        let mut towards = HashMap::<T, T>::new();
+        let mut seen = HashSet::from([id.clone()]);
+        let mut queue = VecDeque::from([id.clone()]);
+
+        while let Some(next) = queue.pop_front() {
+            let dependents = self
+                .dependents
+                .get(&next)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            if dependents.iter().any(|d| self.roots.contains(d)) {
+                let mut path = vec![next.clone()];
+                let mut current = &next;
+                while let Some(n) = towards.get(current) {
+                    path.push(n.clone());
+                    current = n;
+                }
+                result.push(path);
+            }
+
+            for dependent in dependents.iter().filter(|d| !self.roots.contains(*d)) {
+                if seen.insert(dependent.clone()) {
+                    towards.insert(dependent.clone(), next.clone());
+                    queue.push_back(dependent.clone());
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl<T> DependencyGraph<T>
+where
+    T: Clone + Eq + Hash + Ord,
+{
+    /// Find out how all packages get pulled in, walking the graph once from each direct
+    /// dependency.
+    pub fn introductions(&self) -> Introductions<T> {
+        // the graph is stored in reverse, but walking it starts with the direct dependencies
+        let mut dependencies = HashMap::<&T, Vec<&T>>::new();
+        for (dependent, dependency) in self.edges() {
+            dependencies.entry(dependent).or_default().push(dependency);
+        }
+
+        // sorted, so that the outcome doesn't depend on the order of the hash map
+        let mut directs = self
+            .dependents
+            .keys()
+            .filter(|id| self.is_direct(id))
+            .collect::<Vec<_>>();
+        directs.sort();
+
+        let mut introduced_by = HashMap::<T, Vec<(usize, T)>>::new();
+        for direct in &directs {
+            let (distances, _) = self.walk(&dependencies, &[direct]);
+            for (id, distance) in distances {
+                introduced_by
+                    .entry(id.clone())
+                    .or_default()
+                    .push((distance, (*direct).clone()));
+            }
+        }
+
+        // the stable sort keeps the order of the directs for the same distance
+        let introduced_by = introduced_by
+            .into_iter()
+            .map(|(id, mut directs)| {
+                directs.sort_by_key(|(distance, _)| *distance);
+                (id, directs.into_iter().map(|(_, direct)| direct).collect())
+            })
+            .collect();
+
+        let (_, previous) = self.walk(&dependencies, &directs);
+        let previous = previous
+            .into_iter()
+            .map(|(id, previous)| (id.clone(), previous.clone()))
+            .collect();
+
+        Introductions {
+            roots: self.roots.clone(),
+            dependents: self.dependents.clone(),
+            introduced_by,
+            previous,
+        }
+    }
+
+    /// Walk the graph breadth first, starting with some direct dependencies.
+    ///
+    /// Returns the distance of each reached package, and the previous package on the shortest
+    /// path to it. The packages of the project itself are not walked through.
+    fn walk<'a>(
+        &'a self,
+        dependencies: &HashMap<&'a T, Vec<&'a T>>,
+        start: &[&'a T],
+    ) -> (HashMap<&'a T, usize>, HashMap<&'a T, &'a T>) {
+        let mut distances = start.iter().map(|id| (*id, 0)).collect::<HashMap<_, _>>();
+        let mut previous = HashMap::new();
+        let mut queue = start.iter().copied().collect::<VecDeque<_>>();
+
+        while let Some(next) = queue.pop_front() {
+            if self.roots.contains(next) {
+                continue;
+            }
+            let distance = distances[next] + 1;
+            for dependency in dependencies.get(next).into_iter().flatten() {
+                if !distances.contains_key(dependency) {
+                    distances.insert(*dependency, distance);
+                    previous.insert(*dependency, next);
+                    queue.push_back(*dependency);
+                }
+            }
+        }
+
+        (distances, previous)
+    }
+}
+
+/// The maximum number of paths reported for a single package, as they may grow exponentially
+/// with the size of the graph.
+pub const MAX_PATHS: usize = 100;
+
+/// How the packages of a graph get pulled in, computed once for all packages.
+#[derive(Clone, Debug)]
+pub struct Introductions<T = Url> {
+    roots: HashSet<T>,
+    dependents: HashMap<T, Vec<T>>,
+    /// The direct dependencies pulling in a package, the nearest ones first
+    introduced_by: HashMap<T, Vec<T>>,
+    /// The previous package on the shortest path from a direct dependency
+    previous: HashMap<T, T>,
+}
+
+impl<T> Introductions<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// The direct dependencies which pull in a package, the nearest ones first.
+    ///
+    /// If the package is a direct dependency itself, it will be the first one.
+    pub fn introduced_by(&self, id: &T) -> &[T] {
+        self.introduced_by
+            .get(id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The shortest path pulling in a package, empty if the package isn't pulled in.
+    ///
+    /// The path starts with a direct dependency, and ends with the package itself.
+    pub fn shortest_path(&self, id: &T) -> Vec<T> {
+        if !self.introduced_by.contains_key(id) {
+            return vec![];
+        }
+
+        let mut path = vec![id.clone()];
+        let mut current = id;
+        while let Some(previous) = self.previous.get(current) {
+            path.push(previous.clone());
+            current = previous;
+        }
+        path.reverse();
+        path
+    }
+
+    /// All paths pulling in a package, the shortest ones first, up to [`MAX_PATHS`].
+    ///
+    /// Each path starts with a direct dependency, and ends with the package itself. The first
+    /// one is the [shortest path](Self::shortest_path).
+    pub fn paths(&self, id: &T) -> Vec<Vec<T>> {
+        let shortest = self.shortest_path(id);
+        if shortest.is_empty() {
+            return vec![];
+        }
+
+        // walk up to the direct dependencies, in reverse, skipping cycles
+        let mut paths = vec![];
+        let mut pending = vec![vec![id.clone()]];
+        while let Some(path) = pending.pop() {
+            if paths.len() >= MAX_PATHS {
+                break;
+            }
+
+            let next = &path[path.len() - 1];
+            let dependents = self
+                .dependents
+                .get(next)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            if dependents.iter().any(|d| self.roots.contains(d)) {
+                let mut found = path.clone();
+                found.reverse();
+                if found != shortest {
+                    paths.push(found);
+                }
+            }
+
+            for dependent in dependents
+                .iter()
+                .filter(|d| !self.roots.contains(*d) && !path.contains(*d))
+            {
+                let mut path = path.clone();
+                path.push(dependent.clone());
+                pending.push(path);
+            }
+        }
+
+        paths.truncate(MAX_PATHS - 1);
+        paths.sort_by_key(Vec::len);
+        paths.insert(0, shortest);
+        paths
+    }
+}
+
+impl Introductions<Url> {
+    /// Add the information of the graph to a dependency.
+    pub fn annotate(&self, dependency: &mut Dependency) {
+        let introduced_by = self.introduced_by(&dependency.purl);
+        let paths = self.paths(&dependency.purl);
+        dependency.direct = Some(introduced_by.first() == Some(&dependency.purl));
+        dependency.depth = paths.first().map(Vec::len);
+        dependency.introduced_by = introduced_by.to_vec();
+        dependency.paths = paths;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_paths() {
+        let mut graph = DependencyGraph::new(HashSet::from(["app"]));
+        graph.add("app", "a");
+        graph.add("app", "c");
+        graph.add("a", "b");
+        graph.add("b", "c");
+        graph.add("b", "d");
+
+        assert_eq!(graph.paths(&"d"), vec![vec!["a", "b", "d"]]);
+        assert_eq!(graph.paths(&"c"), vec![vec!["c"], vec!["a", "b", "c"]]);
+        assert_eq!(graph.introduced_by(&"d"), vec!["a"]);
+        assert!(graph.paths(&"app").is_empty());
+    }
+
+    #[test]
+    fn test_introductions() {
+        let mut graph = DependencyGraph::new(HashSet::from(["app"]));
+        graph.add("app", "a");
+        graph.add("app", "c");
+        graph.add("a", "b");
+        graph.add("b", "c");
+        graph.add("b", "d");
+
+        let introductions = graph.introductions();
+        assert_eq!(introductions.shortest_path(&"d"), vec!["a", "b", "d"]);
+        assert_eq!(introductions.shortest_path(&"c"), vec!["c"]);
+        assert_eq!(introductions.introduced_by(&"c"), ["c", "a"]);
+        assert_eq!(introductions.introduced_by(&"d"), ["a"]);
+        assert!(introductions.shortest_path(&"app").is_empty());
+        assert!(introductions.introduced_by(&"app").is_empty());
+
+        assert_eq!(
+            introductions.paths(&"c"),
+            vec![vec!["c"], vec!["a", "b", "c"]]
+        );
+        assert_eq!(introductions.paths(&"d"), vec![vec!["a", "b", "d"]]);
+        assert!(introductions.paths(&"app").is_empty());
+    }
+
+    #[test]
+    fn test_all_paths() {
+        let mut graph = DependencyGraph::new(HashSet::from(["app"]));
+        graph.add("app", "a");
+        graph.add("app", "b");
+        graph.add("a", "c");
+        graph.add("b", "c");
+        graph.add("b", "d");
+        graph.add("d", "c");
+        // a cycle
+        graph.add("c", "d");
+
+        let paths = graph.introductions().paths(&"c");
+        assert_eq!(paths[0], vec!["a", "c"]);
+        assert_eq!(paths.len(), 3);
+        assert!(paths.contains(&vec!["b", "c"]));
+        assert!(paths.contains(&vec!["b", "d", "c"]));
+    }
+
+    #[test]
+    fn test_roots() {
+        let mut graph = DependencyGraph::new(HashSet::from(["core", "web"]));
+        graph.add("core", "a");
+        graph.add("web", "b");
+        graph.add("web", "core");
+        graph.add("a", "c");
+        graph.add("b", "c");
+
+        assert_eq!(graph.roots_of(&"c"), HashSet::from(["core", "web"]));
+        assert_eq!(graph.roots_of(&"core"), HashSet::from(["web"]));
+        assert_eq!(graph.introduced_by_root(&"web", &"c"), vec!["b"]);
+        assert_eq!(graph.introduced_by_root(&"core", &"c"), vec!["a"]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Locating dependencies in a `pom.xml` file
+
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+
+/// Properties of a POM, used to resolve expressions like `${project.version}`.
+struct Properties<'a>(HashMap<String, &'a str>);
+
+impl<'a> Properties<'a> {
+    fn new(project: Node<'a, '_>) -> Self {
+        let mut properties = HashMap::new();
+
+        // inherit from the parent first, so that the project can override
+        for parent in children(project, "parent") {
+            for name in ["groupId", "version"] {
+                if let Some(value) = child_text(parent, name) {
+                    properties.insert(format!("project.{name}"), value);
+                }
+            }
+        }
+
+        for name in ["groupId", "artifactId", "version"] {
+            if let Some(value) = child_text(project, name) {
+                properties.insert(format!("project.{name}"), value);
+            }
+        }
+
+        for property in children(project, "properties").flat_map(|p| p.children()) {
+            if let (true, Some(value)) = (property.is_element(), property.text()) {
+                properties.insert(property.tag_name().name().to_string(), value.trim());
+            }
+        }
+
+        Self(properties)
+    }
+
+    /// Resolve all property expressions of a value.
+    fn resolve(&self, value: &str) -> String {
+        let mut result = String::new();
+        let mut remaining = value;
+
+        while let Some(start) = remaining.find("${") {
+            let Some(end) = remaining[start..].find('}') else {
+                break;
+            };
+            let name = &remaining[start + 2..start + end];
+            result.push_str(&remaining[..start]);
+            match self.0.get(name) {
+                Some(value) => result.push_str(value),
+                None => result.push_str(&remaining[start..=start + end]),
+            }
+            remaining = &remaining[start + end + 1..];
+        }
+
+        result.push_str(remaining);
+        result
+    }
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &'static str) -> Option<&'a str> {
+    children(node, name)
+        .next()
+        .and_then(|n| n.text())
+        .map(str::trim)
+}
+
+/// Find the `<dependency>` element of an artifact.
+///
+/// Regular dependencies are preferred over the ones from profiles, which are preferred over the
+/// ones from the dependency management section. Dependencies of plugins are ignored.
+pub fn find_dependency<'a, 'input>(
+    doc: &'a Document<'input>,
+    group_id: &str,
+    artifact_id: &str,
+) -> Option<Node<'a, 'input>> {
+    let properties = Properties::new(doc.root_element());
+
+    let matches = |dependency: &Node| {
+        let value = |name| {
+            child_text(*dependency, name)
+                .map(|value| properties.resolve(value))
+                .unwrap_or_default()
+        };
+        value("groupId") == group_id && value("artifactId") == artifact_id
+    };
+
+    doc.descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "dependency")
+        .filter(|n| {
+            n.parent_element()
+                .map(|p| p.tag_name().name() == "dependencies")
+                .unwrap_or_default()
+        })
+        .filter_map(|n| rank(n).map(|rank| (rank, n)))
+        .filter(|(_, n)| matches(n))
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, n)| n)
+}
+
+/// Rank the location of a dependency element, `None` if it should be ignored.
+fn rank(dependency: Node) -> Option<usize> {
+    let mut rank = 0;
+    for ancestor in dependency.ancestors().filter(Node::is_element) {
+        match ancestor.tag_name().name() {
+            "plugin" => return None,
+            "profile" => rank += 1,
+            "dependencyManagement" => rank += 2,
+            _ => {}
+        }
+    }
+    Some(rank)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_dependency() {
+        let doc = Document::parse(include_str!("../../../../test-data/pom1.xml")).unwrap();
+
+        let gson = find_dependency(&doc, "com.google.code.gson", "gson").unwrap();
+        assert_eq!(
+            gson.parent_element().unwrap().tag_name().name(),
+            "dependencies"
+        );
+        assert_eq!(
+            gson.ancestors()
+                .nth(2)
+                .map(|n| n.tag_name().name().to_string()),
+            Some("project".to_string())
+        );
+
+        // resolved through the properties
+        let bom = find_dependency(&doc, "io.quarkus", "quarkus-bom").unwrap();
+        assert_eq!(
+            bom.ancestors()
+                .nth(2)
+                .map(|n| n.tag_name().name().to_string()),
+            Some("dependencyManagement".to_string())
+        );
+
+        // plugins are not dependencies
+        assert!(find_dependency(&doc, "io.quarkus", "quarkus-maven-plugin").is_none());
+        assert!(find_dependency(&doc, "com.example", "unknown").is_none());
+    }
+}
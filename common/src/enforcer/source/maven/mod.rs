@@ -1,12 +1,21 @@
-use crate::enforcer::{source::Source, Dependency};
-use crate::highlight::Range;
+use crate::enforcer::{
+    source::{self, graph::DependencyGraph, Module, Source},
+    Dependency,
+};
+use crate::highlight::{Highlighter, Range};
+use crate::utils::projects::MAVEN_FILE;
 use anyhow::anyhow;
 use async_trait::async_trait;
+use resolver::{Model, Resolver};
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use url::Url;
 
+pub mod highlight;
 mod pom;
+mod resolver;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MavenDependency {
@@ -19,53 +28,311 @@ pub struct MavenDependency {
     pub classifier: Option<String>,
 }
 
-impl From<pom::Dependency> for MavenDependency {
-    fn from(value: pom::Dependency) -> Self {
-        Self {
-            group_id: value.group_id,
-            artifact_id: value.artifact_id,
-            version: value.version,
-            r#type: value.r#type,
-            classifier: None,
+impl MavenDependency {
+    /// Parse a Maven Package URL, like `pkg:maven/group/artifact@version?type=jar`.
+    pub fn from_purl(purl: &Url) -> Option<Self> {
+        let (name, version) = purl.path().strip_prefix("maven/")?.split_once('@')?;
+        let (group_id, artifact_id) = name.rsplit_once('/')?;
+
+        let qualifier = |name: &str| {
+            purl.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.to_string())
+        };
+
+        Some(Self {
+            group_id: group_id.to_string(),
+            artifact_id: artifact_id.to_string(),
+            version: version.to_string(),
+            r#type: qualifier("type"),
+            classifier: qualifier("classifier"),
+        })
+    }
+}
+
+/// The local repository, as configured by the user's `settings.xml`, or `~/.m2/repository`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn local_repository() -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    let m2 = home.join(".m2");
+
+    let configured = fs::read_to_string(m2.join("settings.xml"))
+        .ok()
+        .and_then(|settings| {
+            let doc = roxmltree::Document::parse(&settings).ok()?;
+            let repository = doc
+                .root_element()
+                .children()
+                .find(|n| n.tag_name().name() == "localRepository")?
+                .text()?
+                .trim()
+                .replace("${user.home}", &home.to_string_lossy());
+            Some(PathBuf::from(repository))
+        });
+
+    Some(configured.unwrap_or_else(|| m2.join("repository")))
+}
+
+/// The local repository, as configured by the user's `settings.xml`, or `~/.m2/repository`
+#[cfg(target_arch = "wasm32")]
+pub fn local_repository() -> Option<PathBuf> {
+    None
+}
+
+/// Find the POM files of all modules of a build, starting with the project itself.
+///
+/// Modules are discovered recursively, following the `<modules>` of each POM, which point to the
+/// directory of a module, or to its POM file.
+pub fn module_manifests(root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    let mut seen = HashSet::new();
+    let mut pending = vec![root.join(MAVEN_FILE)];
+
+    while let Some(manifest) = pending.pop() {
+        if !seen.insert(manifest.canonicalize().unwrap_or_else(|_| manifest.clone())) {
+            continue;
+        }
+
+        let project = resolver::read(&manifest)?;
+        let dir = manifest.parent().unwrap_or(root);
+        // in reverse, so that modules are visited in the order they are declared
+        for module in project.modules.module.iter().rev() {
+            pending.push(pom_file(dir.join(module.trim())));
+        }
+        result.push(manifest);
+    }
+
+    Ok(result)
+}
+
+/// Find the POM files of the parents the modules are built with, following their
+/// `<relativePath>`, which may be outside of the build.
+///
+/// Parents only found in the local repository aren't included.
+pub fn parent_manifests(manifests: &[PathBuf]) -> Vec<PathBuf> {
+    let mut result = vec![];
+    let mut seen = manifests
+        .iter()
+        .map(|manifest| manifest.canonicalize().unwrap_or_else(|_| manifest.clone()))
+        .collect::<HashSet<_>>();
+    let mut pending = manifests.to_vec();
+
+    while let Some(manifest) = pending.pop() {
+        // the generator will report a broken POM
+        let Ok(pom::Project {
+            parent: Some(parent),
+            ..
+        }) = resolver::read(&manifest)
+        else {
+            continue;
+        };
+        let dir = manifest.parent().unwrap_or(Path::new(""));
+
+        // an empty path disables the lookup
+        let relative_path = parent.relative_path.as_deref().unwrap_or("../pom.xml");
+        if relative_path.is_empty() {
+            continue;
         }
+        let Ok(path) = pom_file(dir.join(relative_path)).canonicalize() else {
+            continue;
+        };
+        if path.is_file() && seen.insert(path.clone()) {
+            result.push(path.clone());
+            pending.push(path);
+        }
+    }
+
+    result
+}
+
+/// The POM file of a path pointing to a project, which may be its directory
+fn pom_file(path: PathBuf) -> PathBuf {
+    if path.is_dir() {
+        path.join(MAVEN_FILE)
+    } else {
+        path
+    }
+}
+
+/// Load all modules of a build, and their effective models.
+fn load_modules(root: &Path, resolver: &mut Resolver) -> anyhow::Result<Vec<(Module, Model)>> {
+    let mut result = vec![];
+
+    for manifest in module_manifests(root)? {
+        let project = resolver.project(&manifest)?;
+        let dir = manifest.parent().unwrap_or(root);
+        let path = dir.strip_prefix(root).unwrap_or(dir);
+        resolver.add_module(&project, path.to_string_lossy());
+        result.push((
+            Module {
+                purl: project.purl()?,
+                manifest,
+            },
+            project,
+        ));
     }
+
+    Ok(result)
 }
 
-#[deprecated(
-    note = "This implementation ignores all kinds of cases, use the SBOM version in combination with the Maven source instead."
-)]
+/// Find all modules of a build, starting with the project itself.
+pub fn modules(root: &Path) -> anyhow::Result<Vec<Module>> {
+    // parents outside of the build are only needed for the coordinates of a module
+    let mut resolver = Resolver::new(local_repository().unwrap_or_default());
+    Ok(load_modules(root, &mut resolver)?
+        .into_iter()
+        .map(|(module, _)| module)
+        .collect())
+}
+
+/// Find the range to highlight in a POM file, which may be of a module.
+///
+/// Candidates are tried in order, falling back to the `dependencies` section.
+pub fn highlight(manifest: &Path, candidates: &[Dependency]) -> anyhow::Result<(Url, Range)> {
+    let content = fs::read_to_string(manifest)?;
+    let h = Highlighter::new(&content)?;
+    let url = Url::from_file_path(manifest).map_err(|()| anyhow!("Failed to create file URL"))?;
+
+    // find the actual dependency, or the one which pulled it in
+    for dependency in candidates
+        .iter()
+        .filter_map(|d| MavenDependency::from_purl(&d.purl))
+    {
+        if let Some(position) = h.find_with(|doc| {
+            highlight::find_dependency(doc, &dependency.group_id, &dependency.artifact_id)
+        })? {
+            return Ok((url, position));
+        }
+    }
+
+    let position = h
+        // find the main dependencies section
+        .find_with(|doc| {
+            doc.root_element()
+                .children()
+                .find(|p| p.tag_name().name() == "dependencies")
+        })
+        // then fall back to dependencies section
+        .or_else(|_| {
+            h.find_with(|doc| {
+                doc.root_element()
+                    .children()
+                    .find(|p| p.tag_name().name() == "dependencyManagement")
+                    .and_then(|d| d.children().find(|p| p.tag_name().name() == "dependencies"))
+            })
+        })?
+        // or the full document
+        .unwrap_or_else(|| h.full_range());
+
+    Ok((url, position))
+}
+
+/// A Maven project, resolved from the POMs of the local repository.
+///
+/// Unlike the [`MavenGenerator`](crate::enforcer::source::sbom::maven::MavenGenerator), this
+/// doesn't run Maven, and so doesn't download anything. Dependencies which were never downloaded
+/// by a build are reported, but not resolved any further.
+///
+/// Each module of a multi-module build is a root of the graph.
 pub struct MavenSource {
     root: PathBuf,
+    graph: RwLock<DependencyGraph>,
+    modules: RwLock<Vec<Module>>,
 }
 
-#[allow(deprecated)]
 impl MavenSource {
     pub fn new(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self {
+            root: root.into(),
+            graph: Default::default(),
+            modules: Default::default(),
+        }
+    }
+
+    fn resolve(&self) -> anyhow::Result<(Vec<Dependency>, DependencyGraph, Vec<Module>)> {
+        let repository = local_repository()
+            .ok_or_else(|| anyhow!("Unable to locate the local Maven repository"))?;
+        log::debug!("Using local repository: {}", repository.display());
+
+        let mut resolver = Resolver::new(repository);
+        let (modules, projects): (Vec<_>, Vec<_>) =
+            load_modules(&self.root, &mut resolver)?.into_iter().unzip();
+
+        let (dependencies, graph) = resolver.resolve(&projects)?;
+        Ok((dependencies, graph, modules))
     }
 }
 
-#[allow(deprecated)]
 #[async_trait]
 impl Source for MavenSource {
     async fn scan(&self) -> anyhow::Result<Vec<Dependency>> {
-        let content = fs::read_to_string(self.root.join("pom.xml"))?;
+        let (dependencies, graph, modules) = self.resolve()?;
 
-        let project: pom::Project = quick_xml::de::from_str(&content)?;
+        *self.graph.write().unwrap() = graph;
+        *self.modules.write().unwrap() = modules;
 
-        Ok(project
-            .dependencies
-            .dependency
+        Ok(dependencies)
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        self.graph.read().unwrap().clone()
+    }
+
+    fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
+        let mut introduced_by = self
+            .graph
+            .read()
+            .unwrap()
+            .introduced_by(&dependency.purl)
             .into_iter()
-            .map(|d| d.try_into())
-            .collect::<Result<Vec<Dependency>, _>>()?)
+            .map(Dependency::new)
+            .collect::<Vec<_>>();
+
+        if introduced_by.is_empty() {
+            introduced_by.push(dependency.clone());
+        }
+
+        highlight(&self.root.join(MAVEN_FILE), &introduced_by)
+    }
+
+    fn modules(&self) -> Vec<Module> {
+        self.modules.read().unwrap().clone()
     }
 
-    fn highlight(&self, _dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
-        Ok((
-            Url::from_file_path(self.root.join("pom.xml"))
-                .map_err(|()| anyhow!("Failed to build path URI"))?,
-            Range::default(),
-        ))
+    fn highlight_modules(&self, dependency: &Dependency) -> anyhow::Result<Vec<(Url, Range)>> {
+        let result = source::highlight_modules(
+            &self.graph.read().unwrap(),
+            &self.modules.read().unwrap(),
+            dependency,
+            |module, introduced_by| highlight(&module.manifest, introduced_by),
+        )?;
+
+        if result.is_empty() {
+            Ok(vec![self.highlight(dependency)?])
+        } else {
+            Ok(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_highlight_module() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/maven/nested/build");
+        let modules = modules(&root).unwrap();
+        let lib = modules
+            .iter()
+            .find(|module| module.purl.path() == "maven/com.example/nested-lib@1.0")
+            .unwrap();
+
+        // the module's own POM file, which isn't named `pom.xml`
+        let (url, _) = highlight(&lib.manifest, &[]).unwrap();
+        assert_eq!(
+            url,
+            Url::from_file_path(root.join("lib/lib-pom.xml")).unwrap()
+        );
     }
 }
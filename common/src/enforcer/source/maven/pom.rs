@@ -1,17 +1,59 @@
+use std::collections::BTreeMap;
 use url::{ParseError, Url};
 
 #[derive(Clone, Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Project {
-    pub model_version: String,
+    #[serde(default)]
+    pub parent: Option<Parent>,
+
+    /// Inherited from the parent, if missing
+    #[serde(default)]
+    pub group_id: Option<String>,
+    pub artifact_id: String,
+    /// Inherited from the parent, if missing
+    #[serde(default)]
+    pub version: Option<String>,
+
+    /// The modules of a multi-module build, as paths relative to the project
+    #[serde(default)]
+    pub modules: Modules,
+
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub dependency_management: Option<DependencyManagement>,
+    #[serde(default)]
+    pub dependencies: Dependencies,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Parent {
     pub group_id: String,
     pub artifact_id: String,
     pub version: String,
+    /// The path of the parent project, defaults to `../pom.xml`
+    #[serde(default)]
+    pub relative_path: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Modules {
+    #[serde(default)]
+    pub module: Vec<String>,
+}
 
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyManagement {
+    #[serde(default)]
     pub dependencies: Dependencies,
 }
 
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, Default, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Dependencies {
     #[serde(default)]
@@ -23,14 +65,53 @@ pub struct Dependencies {
 pub struct Dependency {
     pub group_id: String,
     pub artifact_id: String,
+    /// Provided by the dependency management, if missing
     #[serde(default)]
-    pub version: String,
+    pub version: Option<String>,
     #[serde(default)]
     pub r#type: Option<String>,
     #[serde(default)]
     pub classifier: Option<String>,
+    /// Provided by the dependency management, if missing
+    #[serde(default)]
+    pub scope: Option<Scope>,
+    #[serde(default)]
+    pub optional: Option<String>,
     #[serde(default)]
-    pub scope: Scope,
+    pub exclusions: Exclusions,
+}
+
+impl Dependency {
+    /// The type of the dependency, defaults to `jar`
+    pub fn r#type(&self) -> &str {
+        self.r#type.as_deref().unwrap_or("jar")
+    }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(self.optional.as_deref(), Some(optional) if optional.trim() == "true")
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exclusions {
+    #[serde(default)]
+    pub exclusion: Vec<Exclusion>,
+}
+
+/// An excluded transitive dependency, where either ID may be the wildcard `*`
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exclusion {
+    pub group_id: String,
+    pub artifact_id: String,
+}
+
+impl Exclusion {
+    pub fn matches(&self, group_id: &str, artifact_id: &str) -> bool {
+        (self.group_id == "*" || self.group_id == group_id)
+            && (self.artifact_id == "*" || self.artifact_id == artifact_id)
+    }
 }
 
 impl TryFrom<Dependency> for crate::enforcer::dependency::Dependency {
@@ -39,7 +120,9 @@ impl TryFrom<Dependency> for crate::enforcer::dependency::Dependency {
     fn try_from(value: Dependency) -> Result<Self, Self::Error> {
         let mut purl = Url::parse(&format!(
             "pkg:maven/{}/{}@{}",
-            value.group_id, value.artifact_id, value.version,
+            value.group_id,
+            value.artifact_id,
+            value.version.as_deref().unwrap_or_default(),
         ))?;
 
         if let Some(r#type) = &value.r#type {
@@ -49,15 +132,17 @@ impl TryFrom<Dependency> for crate::enforcer::dependency::Dependency {
             purl.query_pairs_mut().append_pair("classifier", classifier);
         }
 
-        // FIXME: deal with missing values, like group id or version
         // FIXME: deal with repository URL
 
-        Ok(Self { purl })
+        let mut dependency = Self::new(purl);
+        dependency.scope = value.scope.unwrap_or_default().into();
+
+        Ok(dependency)
     }
 }
 
-#[derive(Clone, Debug, Default, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(try_from = "String")]
 pub enum Scope {
     #[default]
     Compile,
@@ -68,6 +153,38 @@ pub enum Scope {
     Import,
 }
 
+// the text of an element, which `quick_xml` doesn't deserialize into an enum
+impl TryFrom<String> for Scope {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(match value.trim() {
+            "compile" => Self::Compile,
+            "provided" => Self::Provided,
+            "runtime" => Self::Runtime,
+            "test" => Self::Test,
+            "system" => Self::System,
+            "import" => Self::Import,
+            _ => return Err(format!("Unknown scope: {value}")),
+        })
+    }
+}
+
+impl From<Scope> for Option<crate::enforcer::Scope> {
+    fn from(value: Scope) -> Self {
+        use crate::enforcer::Scope as S;
+        match value {
+            Scope::Compile => Some(S::Compile),
+            Scope::Provided => Some(S::Provided),
+            Scope::Runtime => Some(S::Runtime),
+            Scope::Test => Some(S::Test),
+            Scope::System => Some(S::System),
+            // imports a BOM, which is not a dependency itself
+            Scope::Import => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -81,14 +198,35 @@ mod test {
          xmlns="http://maven.apache.org/POM/4.0.0"
          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
     <modelVersion>4.0.0</modelVersion>
-    <groupId>com.example</groupId>
+    <parent>
+        <groupId>com.example</groupId>
+        <artifactId>parent</artifactId>
+        <version>1.0-SNAPSHOT</version>
+    </parent>
     <artifactId>demo</artifactId>
-    <version>1.0-SNAPSHOT</version>
+
+    <properties>
+        <quarkus.version>3.2.0.Final</quarkus.version>
+        <maven.compiler.release>17</maven.compiler.release>
+    </properties>
 
     <dependencies>
         <dependency>
             <groupId>io.quarkus</groupId>
             <artifactId>quarkus-funqy-amazon-lambda</artifactId>
+            <exclusions>
+                <exclusion>
+                    <groupId>*</groupId>
+                    <artifactId>*</artifactId>
+                </exclusion>
+            </exclusions>
+        </dependency>
+        <dependency>
+            <groupId>junit</groupId>
+            <artifactId>junit</artifactId>
+            <version>4.13.2</version>
+            <scope>test</scope>
+            <optional>true</optional>
         </dependency>
     </dependencies>
 
@@ -96,6 +234,16 @@ mod test {
 "#,
         ).unwrap();
 
-        println!("Result: {project:#?}")
+        assert_eq!(project.group_id, None);
+        assert_eq!(project.parent.unwrap().artifact_id, "parent");
+        assert_eq!(project.properties["quarkus.version"], "3.2.0.Final");
+
+        let [lambda, junit] = &project.dependencies.dependency[..] else {
+            panic!("Expected two dependencies");
+        };
+        assert_eq!(lambda.version, None);
+        assert!(lambda.exclusions.exclusion[0].matches("io.quarkus", "quarkus-core"));
+        assert_eq!(junit.scope, Some(Scope::Test));
+        assert!(junit.is_optional());
     }
 }
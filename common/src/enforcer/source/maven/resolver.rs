@@ -0,0 +1,757 @@
+//! Resolving the dependencies of a Maven project, without running Maven.
+//!
+//! The effective model of a POM is built by merging its parents, interpolating `${property}`
+//! expressions, and applying the dependency management (including imported BOMs). Transitive
+//! dependencies are resolved breadth first, so that the nearest declaration of an artifact wins,
+//! the same way Maven mediates versions.
+//!
+//! POMs are only read from the local repository. As the dependencies of a missing POM can't be
+//! resolved, and so would not get evaluated, missing POMs fail the resolution.
+
+use super::pom::{self, Exclusion, Scope};
+use crate::enforcer::{source::graph::DependencyGraph, Dependency, Origin};
+use crate::utils::projects::MAVEN_FILE;
+use anyhow::bail;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use url::Url;
+
+/// The maximum depth of nested property expressions
+const MAX_INTERPOLATION_DEPTH: usize = 10;
+/// The maximum number of missing POMs listed in an error
+const MAX_REPORTED_MISSING: usize = 10;
+
+/// The coordinates of a POM: group, artifact, and version
+type Coordinates = (String, String, String);
+
+/// The identity of a dependency, for management and mediation: group, artifact, type, and classifier
+type Key = (String, String, String, Option<String>);
+
+fn key(dependency: &pom::Dependency) -> Key {
+    (
+        dependency.group_id.clone(),
+        dependency.artifact_id.clone(),
+        dependency.r#type().to_string(),
+        dependency.classifier.clone(),
+    )
+}
+
+/// A POM merged with its parents, not yet interpolated.
+#[derive(Clone, Debug, Default)]
+struct Inherited {
+    group_id: Option<String>,
+    artifact_id: String,
+    version: Option<String>,
+    parent: Option<pom::Parent>,
+    properties: BTreeMap<String, String>,
+    /// The managed dependencies, the ones of the project first
+    management: Vec<pom::Dependency>,
+    /// The dependencies, the ones of the project first
+    dependencies: Vec<pom::Dependency>,
+}
+
+/// The effective model of a POM.
+#[derive(Clone, Debug, Default)]
+pub struct Model {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+    /// The managed dependencies, including the ones of imported BOMs
+    pub management: HashMap<Key, pom::Dependency>,
+    /// The dependencies, with the management applied
+    pub dependencies: Vec<pom::Dependency>,
+}
+
+impl Model {
+    /// The Package URL of the project itself
+    pub fn purl(&self) -> Result<Url, url::ParseError> {
+        Url::parse(&format!(
+            "pkg:maven/{}/{}@{}",
+            self.group_id, self.artifact_id, self.version
+        ))
+    }
+}
+
+/// A dependency, waiting to be resolved
+struct Node {
+    dependent: Url,
+    dependency: pom::Dependency,
+    scope: Scope,
+    /// The exclusions of the dependency, and all dependencies on the path to it
+    exclusions: Vec<Exclusion>,
+}
+
+pub struct Resolver {
+    repository: PathBuf,
+    /// POMs of the repository, `None` if they are missing
+    inherited: HashMap<Coordinates, Option<Rc<Inherited>>>,
+    models: HashMap<Coordinates, Option<Rc<Model>>>,
+    /// POMs currently being loaded, to break cycles of parents and imports
+    loading: HashSet<Coordinates>,
+    importing: HashSet<Coordinates>,
+    /// The modules of the build, and their paths
+    modules: HashMap<Coordinates, String>,
+    /// POMs which could not be read from the repository
+    missing: BTreeSet<Coordinates>,
+}
+
+impl Resolver {
+    pub fn new(repository: impl Into<PathBuf>) -> Self {
+        Self {
+            repository: repository.into(),
+            inherited: Default::default(),
+            models: Default::default(),
+            loading: Default::default(),
+            importing: Default::default(),
+            modules: Default::default(),
+            missing: Default::default(),
+        }
+    }
+
+    /// Add a module of the build, so that other modules depending on it use the module, rather
+    /// than a POM of the repository.
+    pub fn add_module(&mut self, model: &Model, path: impl Into<String>) {
+        let coordinates = (
+            model.group_id.clone(),
+            model.artifact_id.clone(),
+            model.version.clone(),
+        );
+        self.models
+            .insert(coordinates.clone(), Some(Rc::new(model.clone())));
+        self.modules.insert(coordinates, path.into());
+    }
+
+    /// Load the effective model of a project's `pom.xml`.
+    pub fn project(&mut self, path: &Path) -> anyhow::Result<Model> {
+        let project = read(path)?;
+        let inherited = self.inherit(project, path.parent());
+        Ok(self.effective(&inherited))
+    }
+
+    /// Resolve all dependencies of the projects of a build, and their graph.
+    ///
+    /// Each project is a root of the graph, with its dependencies mediated on their own. Fails if
+    /// any POM (of a dependency, parent, or imported BOM) is missing in the repository.
+    pub fn resolve(
+        &mut self,
+        projects: &[Model],
+    ) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
+        let roots = projects
+            .iter()
+            .map(Model::purl)
+            .collect::<Result<HashSet<_>, _>>()?;
+        let mut graph = DependencyGraph::new(roots);
+        let mut dependencies = vec![];
+        // the index of each dependency, and the scope it was reported with
+        let mut seen = HashMap::new();
+
+        for project in projects {
+            self.resolve_project(project, &mut graph, &mut dependencies, &mut seen)?;
+        }
+
+        if !self.missing.is_empty() {
+            let mut missing = self
+                .missing
+                .iter()
+                .take(MAX_REPORTED_MISSING)
+                .map(|(group_id, artifact_id, version)| {
+                    format!("{group_id}:{artifact_id}:{version}")
+                })
+                .collect::<Vec<_>>();
+            if self.missing.len() > MAX_REPORTED_MISSING {
+                missing.push(format!(
+                    "and {} more",
+                    self.missing.len() - MAX_REPORTED_MISSING
+                ));
+            }
+            bail!(
+                "Missing POMs in the local repository ({}): {}. Build the project once, or use the 'plugin' resolver.",
+                self.repository.display(),
+                missing.join(", ")
+            );
+        }
+
+        let introductions = graph.introductions();
+        for dependency in &mut dependencies {
+            introductions.annotate(dependency);
+        }
+
+        Ok((dependencies, graph))
+    }
+
+    fn resolve_project(
+        &mut self,
+        project: &Model,
+        graph: &mut DependencyGraph,
+        dependencies: &mut Vec<Dependency>,
+        seen: &mut HashMap<Url, (usize, Scope)>,
+    ) -> anyhow::Result<()> {
+        let root = project.purl()?;
+
+        let mut queue = project
+            .dependencies
+            .iter()
+            .filter(|d| d.scope != Some(Scope::Import))
+            .map(|d| Node {
+                dependent: root.clone(),
+                dependency: d.clone(),
+                scope: d.scope.unwrap_or_default(),
+                exclusions: d.exclusions.exclusion.clone(),
+            })
+            .collect::<VecDeque<_>>();
+
+        // the winning version of each artifact, the nearest one
+        let mut resolved = HashMap::<Key, Url>::new();
+
+        while let Some(node) = queue.pop_front() {
+            let id = key(&node.dependency);
+            if let Some(purl) = resolved.get(&id) {
+                graph.add(node.dependent, purl.clone());
+                continue;
+            }
+
+            let Some(version) = node.dependency.version.clone() else {
+                log::warn!(
+                    "Missing version of dependency: {}:{}",
+                    node.dependency.group_id,
+                    node.dependency.artifact_id
+                );
+                continue;
+            };
+
+            let mut dependency: Dependency = pom::Dependency {
+                r#type: Some(node.dependency.r#type().to_string()),
+                scope: Some(node.scope),
+                ..node.dependency.clone()
+            }
+            .try_into()?;
+            let purl = dependency.purl.clone();
+
+            let coordinates = (
+                node.dependency.group_id.clone(),
+                node.dependency.artifact_id.clone(),
+                version.clone(),
+            );
+            if let Some(path) = self.modules.get(&coordinates) {
+                dependency.origin = Some(Origin::Path {
+                    path: Some(path.clone()),
+                });
+            }
+
+            graph.add(node.dependent, purl.clone());
+            resolved.insert(id, purl.clone());
+            // the widest scope of all projects pulling in a dependency wins
+            match seen.get_mut(&purl) {
+                Some((index, scope)) => {
+                    if scope_rank(node.scope) > scope_rank(*scope) {
+                        dependencies[*index] = dependency;
+                        *scope = node.scope;
+                    }
+                }
+                None => {
+                    seen.insert(purl.clone(), (dependencies.len(), node.scope));
+                    dependencies.push(dependency);
+                }
+            }
+
+            // system dependencies are not part of any repository
+            if node.scope == Scope::System {
+                continue;
+            }
+            let Some(model) = self.model(
+                &node.dependency.group_id,
+                &node.dependency.artifact_id,
+                &version,
+            ) else {
+                continue;
+            };
+
+            for child in &model.dependencies {
+                let child_scope = child.scope.unwrap_or_default();
+                if child.is_optional()
+                    || !matches!(child_scope, Scope::Compile | Scope::Runtime)
+                    || node
+                        .exclusions
+                        .iter()
+                        .any(|e| e.matches(&child.group_id, &child.artifact_id))
+                {
+                    continue;
+                }
+
+                let mut child = child.clone();
+                let mut child_scope = child_scope;
+                // the management of the project wins over the one of the dependency, for both
+                // the version and the scope
+                if let Some(managed) = project.management.get(&key(&child)) {
+                    if let Some(version) = &managed.version {
+                        child.version = Some(version.clone());
+                    }
+                    if let Some(scope) = managed.scope {
+                        child_scope = scope;
+                    }
+                }
+
+                let mut exclusions = node.exclusions.clone();
+                exclusions.extend(child.exclusions.exclusion.iter().cloned());
+
+                queue.push_back(Node {
+                    dependent: purl.clone(),
+                    scope: transitive_scope(node.scope, child_scope),
+                    exclusions,
+                    dependency: child,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn pom_path(&self, group_id: &str, artifact_id: &str, version: &str) -> PathBuf {
+        self.repository
+            .join(group_id.replace('.', "/"))
+            .join(artifact_id)
+            .join(version)
+            .join(format!("{artifact_id}-{version}.pom"))
+    }
+
+    /// Merge a project with its parents.
+    fn inherit(&mut self, project: pom::Project, dir: Option<&Path>) -> Inherited {
+        let parent = project
+            .parent
+            .as_ref()
+            .and_then(|parent| self.parent(parent, dir))
+            .unwrap_or_default();
+
+        let mut properties = parent.properties.clone();
+        properties.extend(project.properties);
+
+        let management = project
+            .dependency_management
+            .map(|m| m.dependencies.dependency)
+            .unwrap_or_default()
+            .into_iter()
+            .chain(parent.management.iter().cloned())
+            .collect();
+        let dependencies = project
+            .dependencies
+            .dependency
+            .into_iter()
+            .chain(parent.dependencies.iter().cloned())
+            .collect();
+
+        Inherited {
+            group_id: project
+                .group_id
+                .or_else(|| project.parent.as_ref().map(|p| p.group_id.clone())),
+            artifact_id: project.artifact_id,
+            version: project
+                .version
+                .or_else(|| project.parent.as_ref().map(|p| p.version.clone())),
+            parent: project.parent,
+            properties,
+            management,
+            dependencies,
+        }
+    }
+
+    /// Find the parent, next to the project if it is part of the same build, or in the repository.
+    fn parent(&mut self, parent: &pom::Parent, dir: Option<&Path>) -> Option<Rc<Inherited>> {
+        if let Some(dir) = dir {
+            let relative_path = parent.relative_path.as_deref().unwrap_or("../pom.xml");
+            // an empty path disables the lookup
+            if !relative_path.is_empty() {
+                let mut path = dir.join(relative_path);
+                if path.is_dir() {
+                    path.push(MAVEN_FILE);
+                }
+                if let Ok(project) = read(&path) {
+                    let group_id = project
+                        .group_id
+                        .as_ref()
+                        .or(project.parent.as_ref().map(|p| &p.group_id));
+                    if project.artifact_id == parent.artifact_id
+                        && group_id == Some(&parent.group_id)
+                    {
+                        let dir = path.parent().map(Path::to_path_buf);
+                        return Some(Rc::new(self.inherit(project, dir.as_deref())));
+                    }
+                }
+            }
+        }
+
+        self.inherited(&parent.group_id, &parent.artifact_id, &parent.version)
+    }
+
+    /// Load a POM from the repository, merged with its parents.
+    fn inherited(
+        &mut self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+    ) -> Option<Rc<Inherited>> {
+        let coordinates = (
+            group_id.to_string(),
+            artifact_id.to_string(),
+            version.to_string(),
+        );
+        if let Some(inherited) = self.inherited.get(&coordinates) {
+            return inherited.clone();
+        }
+        if !self.loading.insert(coordinates.clone()) {
+            log::warn!("Cycle of parent POMs: {group_id}:{artifact_id}:{version}");
+            return None;
+        }
+
+        let path = self.pom_path(group_id, artifact_id, version);
+        let inherited = match read(&path) {
+            Ok(project) => Some(Rc::new(self.inherit(project, None))),
+            Err(err) => {
+                log::warn!("Failed to read POM {}: {err}", path.display());
+                self.missing.insert(coordinates.clone());
+                None
+            }
+        };
+
+        self.loading.remove(&coordinates);
+        self.inherited.insert(coordinates, inherited.clone());
+        inherited
+    }
+
+    /// Load the effective model of a POM from the repository.
+    fn model(&mut self, group_id: &str, artifact_id: &str, version: &str) -> Option<Rc<Model>> {
+        let coordinates = (
+            group_id.to_string(),
+            artifact_id.to_string(),
+            version.to_string(),
+        );
+        if let Some(model) = self.models.get(&coordinates) {
+            return model.clone();
+        }
+        if !self.importing.insert(coordinates.clone()) {
+            log::warn!("Cycle of imported POMs: {group_id}:{artifact_id}:{version}");
+            return None;
+        }
+
+        let model = self
+            .inherited(group_id, artifact_id, version)
+            .map(|inherited| Rc::new(self.effective(&inherited)));
+
+        self.importing.remove(&coordinates);
+        self.models.insert(coordinates, model.clone());
+        model
+    }
+
+    /// Interpolate a merged POM, and apply its dependency management.
+    fn effective(&mut self, inherited: &Inherited) -> Model {
+        let mut properties = inherited.properties.clone();
+        let mut builtin = |name: &str, value: Option<&String>| {
+            if let Some(value) = value {
+                properties.insert(name.to_string(), value.clone());
+            }
+        };
+        builtin("project.groupId", inherited.group_id.as_ref());
+        builtin("project.artifactId", Some(&inherited.artifact_id));
+        builtin("project.version", inherited.version.as_ref());
+        builtin(
+            "project.parent.groupId",
+            inherited.parent.as_ref().map(|p| &p.group_id),
+        );
+        builtin(
+            "project.parent.version",
+            inherited.parent.as_ref().map(|p| &p.version),
+        );
+
+        let mut management = HashMap::new();
+        let mut imports = vec![];
+        for dependency in &inherited.management {
+            let dependency = interpolate_dependency(dependency, &properties);
+            if dependency.scope == Some(Scope::Import) {
+                imports.push(dependency);
+            } else {
+                // declared ones win over inherited ones
+                management.entry(key(&dependency)).or_insert(dependency);
+            }
+        }
+
+        // declared and inherited ones win over imported ones, earlier imports over later ones
+        for import in imports {
+            let Some(version) = &import.version else {
+                continue;
+            };
+            if let Some(bom) = self.model(&import.group_id, &import.artifact_id, version) {
+                for (key, dependency) in &bom.management {
+                    management
+                        .entry(key.clone())
+                        .or_insert_with(|| dependency.clone());
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let dependencies = inherited
+            .dependencies
+            .iter()
+            .map(|dependency| interpolate_dependency(dependency, &properties))
+            .filter(|dependency| seen.insert(key(dependency)))
+            .map(|mut dependency| {
+                if let Some(managed) = management.get(&key(&dependency)) {
+                    if dependency.version.is_none() {
+                        dependency.version = managed.version.clone();
+                    }
+                    if dependency.scope.is_none() {
+                        dependency.scope = managed.scope;
+                    }
+                    if dependency.exclusions.exclusion.is_empty() {
+                        dependency.exclusions = managed.exclusions.clone();
+                    }
+                }
+                dependency
+            })
+            .collect();
+
+        let value =
+            |value: &Option<String>| interpolate(value.as_deref().unwrap_or_default(), &properties);
+
+        Model {
+            group_id: value(&inherited.group_id),
+            artifact_id: interpolate(&inherited.artifact_id, &properties),
+            version: value(&inherited.version),
+            management,
+            dependencies,
+        }
+    }
+}
+
+pub(super) fn read(path: &Path) -> anyhow::Result<pom::Project> {
+    Ok(quick_xml::de::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// The scope of a transitive dependency, see "Dependency Scope" of the Maven documentation.
+fn transitive_scope(dependent: Scope, dependency: Scope) -> Scope {
+    match dependent {
+        Scope::Compile => dependency,
+        scope => scope,
+    }
+}
+
+/// How widely a scope makes a dependency available, like Maven chooses between the scopes of an
+/// artifact.
+fn scope_rank(scope: Scope) -> u8 {
+    match scope {
+        Scope::System => 4,
+        Scope::Compile => 3,
+        Scope::Runtime => 2,
+        Scope::Provided => 1,
+        Scope::Test | Scope::Import => 0,
+    }
+}
+
+/// Resolve all property expressions of a value, which may be nested.
+fn interpolate(value: &str, properties: &BTreeMap<String, String>) -> String {
+    let mut value = value.to_string();
+
+    for _ in 0..MAX_INTERPOLATION_DEPTH {
+        if !value.contains("${") {
+            break;
+        }
+
+        let mut result = String::new();
+        let mut remaining = value.as_str();
+        while let Some(start) = remaining.find("${") {
+            let Some(end) = remaining[start..].find('}') else {
+                break;
+            };
+            let name = &remaining[start + 2..start + end];
+            result.push_str(&remaining[..start]);
+            match properties.get(name) {
+                Some(value) => result.push_str(value),
+                None => result.push_str(&remaining[start..=start + end]),
+            }
+            remaining = &remaining[start + end + 1..];
+        }
+        result.push_str(remaining);
+
+        if result == value {
+            break;
+        }
+        value = result;
+    }
+
+    value
+}
+
+fn interpolate_dependency(
+    dependency: &pom::Dependency,
+    properties: &BTreeMap<String, String>,
+) -> pom::Dependency {
+    let value = |value: &Option<String>| value.as_ref().map(|v| interpolate(v, properties));
+
+    pom::Dependency {
+        group_id: interpolate(&dependency.group_id, properties),
+        artifact_id: interpolate(&dependency.artifact_id, properties),
+        version: value(&dependency.version),
+        r#type: value(&dependency.r#type),
+        classifier: value(&dependency.classifier),
+        scope: dependency.scope,
+        optional: value(&dependency.optional),
+        exclusions: dependency.exclusions.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::enforcer::Scope as S;
+
+    #[test]
+    fn test_resolve() {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/maven");
+        let mut resolver = Resolver::new(test_data.join("repository"));
+        let project = resolver
+            .project(&test_data.join("project").join(MAVEN_FILE))
+            .unwrap();
+        assert_eq!(
+            project.purl().unwrap().as_str(),
+            "pkg:maven/com.example/app@1.0"
+        );
+
+        let (dependencies, graph) = resolver.resolve(&[project]).unwrap();
+        let dependencies = dependencies
+            .iter()
+            .map(|d| (d.purl.as_str(), d.scope, d.direct))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            dependencies,
+            vec![
+                // managed by the parent
+                (
+                    "pkg:maven/com.google.guava/guava@31.1-jre?type=jar",
+                    Some(S::Compile),
+                    Some(true)
+                ),
+                (
+                    "pkg:maven/org.example/lib@1.0?type=jar",
+                    Some(S::Runtime),
+                    Some(true)
+                ),
+                (
+                    "pkg:maven/junit/junit@4.13.2?type=jar",
+                    Some(S::Test),
+                    Some(true)
+                ),
+                // the nearest one wins, jsr305 is excluded
+                (
+                    "pkg:maven/com.google.guava/failureaccess@1.0.1?type=jar",
+                    Some(S::Compile),
+                    Some(false)
+                ),
+                // managed by the BOM, imported by the parent
+                (
+                    "pkg:maven/org.slf4j/slf4j-api@2.0.7?type=jar",
+                    Some(S::Runtime),
+                    Some(false)
+                ),
+            ]
+        );
+        assert_eq!(
+            graph
+                .introduced_by(
+                    &Url::parse("pkg:maven/com.google.guava/failureaccess@1.0.1?type=jar").unwrap()
+                )
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_resolve_missing() {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/maven");
+        let mut resolver = Resolver::new(test_data.join("missing"));
+        let project = resolver
+            .project(&test_data.join("project").join(MAVEN_FILE))
+            .unwrap();
+
+        // the parent and all dependencies are missing
+        let err = resolver.resolve(&[project]).unwrap_err().to_string();
+        assert!(err.contains("com.example:parent:1.0"), "{err}");
+        assert!(err.contains("org.example:lib:1.0"), "{err}");
+    }
+
+    #[test]
+    fn test_resolve_scopes() {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/maven");
+        let root = test_data.join("scopes");
+        let mut resolver = Resolver::new(test_data.join("repository"));
+
+        let projects = super::super::module_manifests(&root)
+            .unwrap()
+            .iter()
+            .map(|manifest| resolver.project(manifest).unwrap())
+            .collect::<Vec<_>>();
+        let (dependencies, _) = resolver.resolve(&projects).unwrap();
+        let scope = |purl: &str| {
+            let found = dependencies
+                .iter()
+                .filter(|d| d.purl.as_str() == purl)
+                .collect::<Vec<_>>();
+            assert_eq!(found.len(), 1, "{purl}");
+            found[0].scope
+        };
+
+        // a test dependency of the first module, and a compile one of the second
+        assert_eq!(
+            scope("pkg:maven/junit/junit@4.13.2?type=jar"),
+            Some(S::Compile)
+        );
+        // the management of the project applies to the scope of a transitive dependency
+        assert_eq!(
+            scope("pkg:maven/com.google.guava/failureaccess@1.0.1?type=jar"),
+            Some(S::Provided)
+        );
+    }
+
+    #[test]
+    fn test_resolve_modules() {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/maven");
+        let root = test_data.join("multi");
+        let mut resolver = Resolver::new(test_data.join("repository"));
+
+        let mut projects = vec![];
+        for manifest in super::super::module_manifests(&root).unwrap() {
+            let project = resolver.project(&manifest).unwrap();
+            let path = manifest.parent().unwrap().strip_prefix(&root).unwrap();
+            resolver.add_module(&project, path.to_string_lossy());
+            projects.push(project);
+        }
+        assert_eq!(
+            projects
+                .iter()
+                .map(|p| p.artifact_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["multi", "core", "app"]
+        );
+
+        let (dependencies, graph) = resolver.resolve(&projects).unwrap();
+
+        // a module, depended on by another one
+        let core = dependencies
+            .iter()
+            .find(|d| d.purl.as_str() == "pkg:maven/com.example/core@1.0?type=jar")
+            .unwrap();
+        assert_eq!(
+            core.origin,
+            Some(Origin::Path {
+                path: Some("core".into())
+            })
+        );
+
+        let lib = Url::parse("pkg:maven/org.example/lib@1.0?type=jar").unwrap();
+        let roots = graph.roots_of(&lib);
+        assert_eq!(roots.len(), 2);
+        assert!(roots.contains(&Url::parse("pkg:maven/com.example/core@1.0").unwrap()));
+        assert!(roots.contains(&Url::parse("pkg:maven/com.example/app@1.0").unwrap()));
+    }
+}
@@ -1,11 +1,18 @@
 use crate::enforcer::dependency::Dependency;
+use crate::enforcer::source::graph::DependencyGraph;
 use crate::highlight::Range;
 use async_trait::async_trait;
+use std::path::PathBuf;
 use url::Url;
 
 pub mod cargo;
 mod detect;
+pub mod golang;
+pub mod gradle;
+pub mod graph;
 pub mod maven;
+pub mod npm;
+pub mod python;
 pub mod sbom;
 
 pub use detect::AutoSource;
@@ -16,6 +23,64 @@ pub trait Source: Send {
     /// Scan a source for dependencies
     async fn scan(&self) -> anyhow::Result<Vec<Dependency>>;
 
+    /// The dependency graph of the last scan.
+    ///
+    /// Sources which don't know about the relations of the dependencies return an empty graph.
+    fn graph(&self) -> DependencyGraph;
+
     /// Find the range to highlight for a specified dependency.
     fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)>;
+
+    /// The modules of the last scan, empty for projects without modules.
+    fn modules(&self) -> Vec<Module> {
+        vec![]
+    }
+
+    /// Find the ranges to highlight for a dependency, one in each module pulling it in.
+    ///
+    /// Without modules, this is the range of [`Source::highlight`].
+    fn highlight_modules(&self, dependency: &Dependency) -> anyhow::Result<Vec<(Url, Range)>> {
+        Ok(vec![self.highlight(dependency)?])
+    }
+}
+
+/// A module of a project, like the ones of a Maven multi-module build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Module {
+    /// The Package URL of the module, one of the roots of the dependency graph
+    pub purl: Url,
+    /// The manifest of the module
+    pub manifest: PathBuf,
+}
+
+/// Highlight a dependency in the manifest of each module pulling it in.
+///
+/// The function receives the module, and its direct dependencies pulling in the dependency, the
+/// nearest ones first. The result is empty if the dependency isn't part of any module.
+pub(crate) fn highlight_modules<F>(
+    graph: &DependencyGraph,
+    modules: &[Module],
+    dependency: &Dependency,
+    highlight: F,
+) -> anyhow::Result<Vec<(Url, Range)>>
+where
+    F: Fn(&Module, &[Dependency]) -> anyhow::Result<(Url, Range)>,
+{
+    let roots = graph.roots_of(&dependency.purl);
+
+    modules
+        .iter()
+        .filter(|module| roots.contains(&module.purl))
+        .map(|module| {
+            let mut introduced_by = graph
+                .introduced_by_root(&module.purl, &dependency.purl)
+                .into_iter()
+                .map(Dependency::new)
+                .collect::<Vec<_>>();
+            if introduced_by.is_empty() {
+                introduced_by.push(dependency.clone());
+            }
+            highlight(module, &introduced_by)
+        })
+        .collect()
 }
@@ -0,0 +1,147 @@
+//! The parts of a `package.json` file we need.
+
+use crate::enforcer::Scope;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// The sections of `package.json` declaring dependencies
+const SECTIONS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageJson {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Dependencies, with their version range, by name
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    pub dev_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    pub optional_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    pub peer_dependencies: BTreeMap<String, String>,
+}
+
+impl PackageJson {
+    /// The Package URL of the project itself.
+    pub fn purl(&self) -> anyhow::Result<Url> {
+        super::purl(
+            self.name.as_deref().unwrap_or("root"),
+            self.version.as_deref().unwrap_or("0.0.0"),
+        )
+    }
+
+    /// The direct dependencies, by name, with their version range and how they are used.
+    ///
+    /// A regular dependency wins over an optional or peer dependency, which wins over a dev
+    /// dependency.
+    pub fn direct(&self) -> BTreeMap<&str, (&str, Scope)> {
+        let sections = [
+            (&self.dev_dependencies, Scope::Dev),
+            (&self.peer_dependencies, Scope::Peer),
+            (&self.optional_dependencies, Scope::Optional),
+            (&self.dependencies, Scope::Runtime),
+        ];
+
+        let mut result = BTreeMap::new();
+        for (dependencies, scope) in sections {
+            for (name, range) in dependencies {
+                result.insert(name.as_str(), (range.as_str(), scope));
+            }
+        }
+        result
+    }
+}
+
+/// Get the (unescaped) package name of an npm Package URL.
+pub fn name_from_purl(purl: &Url) -> Option<String> {
+    let (name, _) = purl.path().strip_prefix("npm/")?.rsplit_once('@')?;
+    Some(name.replace("%40", "@"))
+}
+
+/// Find the span of the key declaring the package `name` in one of the dependency sections.
+///
+/// This only scans the JSON structure, as the parser doesn't provide the locations.
+pub fn find_dependency(content: &str, name: &str) -> Option<std::ops::Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    // the last key of the top-level object
+    let mut key = None;
+    // if the object we are in is a dependency section
+    let mut section = false;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                let end = (i + 1).min(bytes.len());
+                let value = &content[start + 1..i.min(bytes.len())];
+
+                if content[end..].trim_start().starts_with(':') {
+                    if depth == 1 {
+                        key = Some(value);
+                    } else if depth == 2 && section && value == name {
+                        return Some(start..end);
+                    }
+                }
+            }
+            b'{' | b'[' => {
+                depth += 1;
+                if depth == 2 {
+                    section =
+                        bytes[i] == b'{' && matches!(key, Some(key) if SECTIONS.contains(&key));
+                }
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_dependency() {
+        let content = r#"{
+  "name": "app",
+  "scripts": { "react": "echo" },
+  "dependencies": {
+    "react": "^18.2.0"
+  },
+  "devDependencies": { "@types/react": "^18" }
+}"#;
+
+        let find = |name| find_dependency(content, name).map(|span| &content[span]);
+
+        assert_eq!(find("react"), Some(r#""react""#));
+        assert_eq!(find("@types/react"), Some(r#""@types/react""#));
+        assert_eq!(find("name"), None);
+        assert_eq!(
+            find_dependency(content, "react"),
+            content
+                .find(r#""react": "^18"#)
+                .map(|start| start..start + 7)
+        );
+    }
+}
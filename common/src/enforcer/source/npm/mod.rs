@@ -0,0 +1,274 @@
+//! Dependencies of npm projects, read from the lockfile of npm, yarn, or pnpm.
+
+use crate::enforcer::{
+    source::{graph::DependencyGraph, Source},
+    Checksum, Dependency, Origin, Scope,
+};
+use crate::highlight::{Range, TextHighlighter};
+use crate::utils::projects::{NPM_FILE, NPM_LOCK_FILE, PNPM_LOCK_FILE, YARN_LOCK_FILE};
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use url::Url;
+
+mod manifest;
+mod package_lock;
+mod pnpm;
+mod yarn;
+
+pub use manifest::PackageJson;
+
+/// A package of a lockfile, independent of the lockfile format
+#[derive(Clone, Debug, Default)]
+struct Package {
+    name: String,
+    version: String,
+    origin: Option<Origin>,
+    /// The Subresource Integrity value, like `sha512-<base64>`
+    integrity: Option<String>,
+    license: Option<String>,
+    /// The keys of the packages this package depends on
+    dependencies: Vec<String>,
+    /// How the package is used, if the lockfile records it
+    scope: Option<Scope>,
+}
+
+/// The content of a lockfile, independent of the lockfile format
+#[derive(Debug, Default)]
+struct Lock {
+    /// The packages, by a key specific to the lockfile format
+    packages: BTreeMap<String, Package>,
+    /// The keys of the direct dependencies, and how they are used
+    direct: Vec<(String, Scope)>,
+}
+
+impl Lock {
+    /// Convert the lockfile into dependencies, and their graph.
+    fn into_dependencies(self, root: Url) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
+        let purls = self
+            .packages
+            .iter()
+            .map(|(key, package)| Ok((key.clone(), purl(&package.name, &package.version)?)))
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+        let mut graph = DependencyGraph::new(HashSet::from([root.clone()]));
+        let mut scopes = HashMap::new();
+
+        for (key, scope) in &self.direct {
+            if let Some(purl) = purls.get(key) {
+                graph.add(root.clone(), purl.clone());
+                scopes.insert(purl.clone(), *scope);
+            }
+        }
+
+        for (key, package) in &self.packages {
+            for dependency in package.dependencies.iter().filter_map(|d| purls.get(d)) {
+                graph.add(purls[key].clone(), dependency.clone());
+            }
+        }
+
+        let introductions = graph.introductions();
+        let mut seen = HashSet::new();
+        let mut dependencies = vec![];
+
+        for (key, package) in self.packages {
+            // the same version of a package may be installed in multiple locations
+            let purl = purls[&key].clone();
+            if !seen.insert(purl.clone()) {
+                continue;
+            }
+
+            let mut dependency = Dependency::new(purl);
+            introductions.annotate(&mut dependency);
+
+            // if the lockfile doesn't tell, inherit the scope of the direct dependencies
+            dependency.scope = package.scope.or_else(|| {
+                dependency
+                    .introduced_by
+                    .iter()
+                    .filter_map(|direct| scopes.get(direct))
+                    .copied()
+                    .min_by_key(|scope| match scope {
+                        Scope::Peer => 1,
+                        Scope::Optional => 2,
+                        Scope::Dev => 3,
+                        _ => 0,
+                    })
+            });
+            dependency.origin = package.origin;
+            dependency.checksums = package
+                .integrity
+                .as_deref()
+                .map(checksums)
+                .unwrap_or_default();
+            dependency.licenses = package.license.into_iter().collect();
+
+            dependencies.push(dependency);
+        }
+
+        Ok((dependencies, graph))
+    }
+}
+
+/// Create the Package URL of an npm package.
+///
+/// The `@` of a scoped package, like `@types/node`, is encoded, as it is part of the namespace.
+fn purl(name: &str, version: &str) -> anyhow::Result<Url> {
+    let name = match name.strip_prefix('@') {
+        Some(name) => format!("%40{name}"),
+        None => name.to_string(),
+    };
+    Ok(Url::parse(&format!("pkg:npm/{name}@{version}"))?)
+}
+
+/// Split a package specification, like `@types/node@^18`, into its name and the rest.
+fn split_name(spec: &str) -> Option<(&str, &str)> {
+    // the name of a scoped package starts with an `@` too
+    let index = spec.get(1..)?.find('@')? + 1;
+    Some((&spec[..index], &spec[index + 1..]))
+}
+
+/// Convert a Subresource Integrity value into checksums.
+fn checksums(integrity: &str) -> Vec<Checksum> {
+    integrity
+        .split_whitespace()
+        .filter_map(|hash| {
+            let (algorithm, value) = hash.split_once('-')?;
+            Some(Checksum {
+                algorithm: algorithm.to_string(),
+                value: hex::encode(STANDARD.decode(value).ok()?),
+            })
+        })
+        .collect()
+}
+
+/// Find the origin of a package, from the URL it was resolved from.
+fn origin_from_url(resolved: &str) -> Option<Origin> {
+    if let Some(path) = resolved
+        .strip_prefix("file:")
+        .or_else(|| resolved.strip_prefix("link:"))
+    {
+        return Some(Origin::Path {
+            path: Some(path.to_string()),
+        });
+    }
+
+    if resolved.starts_with("git") {
+        let url = resolved.strip_prefix("git+").unwrap_or(resolved);
+        let (url, revision) = match url.split_once('#') {
+            Some((url, revision)) => (url, Some(revision.to_string())),
+            None => (url, None),
+        };
+        return Some(Origin::Git {
+            url: url.to_string(),
+            revision,
+        });
+    }
+
+    let url = Url::parse(resolved).ok()?;
+    match url.host_str()? {
+        "registry.npmjs.org" | "registry.yarnpkg.com" => Some(Origin::Registry { url: None }),
+        _ => Some(Origin::Registry {
+            url: Some(url.origin().ascii_serialization()),
+        }),
+    }
+}
+
+pub struct NpmSource {
+    root: PathBuf,
+    graph: RwLock<DependencyGraph>,
+}
+
+impl NpmSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            graph: Default::default(),
+        }
+    }
+
+    /// Read the first lockfile we find.
+    fn lock(&self, manifest: &PackageJson) -> anyhow::Result<Lock> {
+        let read = |name| fs::read_to_string(self.root.join(name));
+
+        if let Ok(content) = read(NPM_LOCK_FILE) {
+            package_lock::parse(&content, manifest)
+        } else if let Ok(content) = read(YARN_LOCK_FILE) {
+            yarn::parse(&content, manifest)
+        } else if let Ok(content) = read(PNPM_LOCK_FILE) {
+            pnpm::parse(&content)
+        } else {
+            bail!(
+                "Missing lockfile, one of {NPM_LOCK_FILE}, {YARN_LOCK_FILE}, or {PNPM_LOCK_FILE} is required"
+            )
+        }
+    }
+}
+
+#[async_trait]
+impl Source for NpmSource {
+    async fn scan(&self) -> anyhow::Result<Vec<Dependency>> {
+        let manifest: PackageJson =
+            serde_json::from_str(&fs::read_to_string(self.root.join(NPM_FILE))?)?;
+
+        let (dependencies, graph) = self.lock(&manifest)?.into_dependencies(manifest.purl()?)?;
+
+        *self.graph.write().unwrap() = graph;
+
+        Ok(dependencies)
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        self.graph.read().unwrap().clone()
+    }
+
+    fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
+        let path = self.root.join(NPM_FILE);
+        let content = fs::read_to_string(&path)?;
+        let h = TextHighlighter::new(&content);
+        let url = Url::from_file_path(&path).map_err(|()| anyhow!("Failed to build path URI"))?;
+
+        // highlight the direct dependency, or the one pulling in the transitive dependency
+        let mut candidates = self.graph.read().unwrap().introduced_by(&dependency.purl);
+        if candidates.is_empty() {
+            candidates.push(dependency.purl.clone());
+        }
+
+        let position = candidates
+            .iter()
+            .filter_map(manifest::name_from_purl)
+            .find_map(|name| {
+                h.find_with(|content| manifest::find_dependency(content, &name))
+                    .transpose()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok((url, position))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_purl() {
+        assert_eq!(
+            purl("@types/node", "18.0.0").unwrap().as_str(),
+            "pkg:npm/%40types/node@18.0.0"
+        );
+        assert_eq!(split_name("@types/node@^18"), Some(("@types/node", "^18")));
+        assert_eq!(
+            checksums("sha1-qUw4fOr8mW+vbe3Dy/TeajF9DWM="),
+            vec![Checksum {
+                algorithm: "sha1".to_string(),
+                value: "a94c387ceafc996faf6dedc3cbf4de6a317d0d63".to_string(),
+            }]
+        );
+    }
+}
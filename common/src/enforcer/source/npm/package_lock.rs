@@ -0,0 +1,264 @@
+//! The `package-lock.json` file of npm.
+//!
+//! Version 1 nests the installed packages in a tree of `dependencies`, while versions 2 and 3
+//! list them in `packages`, by their location in `node_modules`.
+
+use super::{origin_from_url, Lock, Package, PackageJson};
+use crate::enforcer::Scope;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageLock {
+    /// The packages of version 2 and 3, by their path
+    #[serde(default)]
+    packages: BTreeMap<String, Entry>,
+    /// The packages of version 1, by their name
+    #[serde(default)]
+    dependencies: BTreeMap<String, LegacyEntry>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    resolved: Option<String>,
+    #[serde(default)]
+    integrity: Option<String>,
+    /// Usually a string, older packages might still use an object
+    #[serde(default)]
+    license: Option<serde_json::Value>,
+    /// A symbolic link, e.g. to a workspace member
+    #[serde(default)]
+    link: bool,
+    #[serde(default)]
+    dev: bool,
+    #[serde(default)]
+    optional: bool,
+    #[serde(default)]
+    dev_optional: bool,
+    #[serde(default)]
+    peer: bool,
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    peer_dependencies: BTreeMap<String, String>,
+}
+
+impl Entry {
+    fn scope(&self) -> Scope {
+        if self.dev || self.dev_optional {
+            Scope::Dev
+        } else if self.optional {
+            Scope::Optional
+        } else if self.peer {
+            Scope::Peer
+        } else {
+            Scope::Runtime
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyEntry {
+    version: String,
+    #[serde(default)]
+    resolved: Option<String>,
+    #[serde(default)]
+    integrity: Option<String>,
+    #[serde(default)]
+    dev: bool,
+    #[serde(default)]
+    optional: bool,
+    /// The dependencies, by name, with their version range
+    #[serde(default)]
+    requires: BTreeMap<String, String>,
+    /// Packages installed in the `node_modules` directory of this package
+    #[serde(default)]
+    dependencies: BTreeMap<String, LegacyEntry>,
+}
+
+pub(super) fn parse(content: &str, manifest: &PackageJson) -> anyhow::Result<Lock> {
+    let mut lock: PackageLock = serde_json::from_str(content)?;
+
+    if lock.packages.is_empty() {
+        flatten(&mut lock.packages, "", lock.dependencies);
+    }
+
+    // the root entry is missing in version 1
+    let root = lock.packages.remove("").unwrap_or_else(|| Entry {
+        dependencies: manifest.dependencies.clone(),
+        dev_dependencies: manifest.dev_dependencies.clone(),
+        optional_dependencies: manifest.optional_dependencies.clone(),
+        peer_dependencies: manifest.peer_dependencies.clone(),
+        ..Default::default()
+    });
+
+    let sections = [
+        (&root.dev_dependencies, Scope::Dev),
+        (&root.peer_dependencies, Scope::Peer),
+        (&root.optional_dependencies, Scope::Optional),
+        (&root.dependencies, Scope::Runtime),
+    ];
+    let direct = sections
+        .into_iter()
+        .flat_map(|(dependencies, scope)| dependencies.keys().map(move |name| (name, scope)))
+        .filter_map(|(name, scope)| Some((resolve(&lock.packages, "", name)?.to_string(), scope)))
+        .collect();
+
+    let mut packages = BTreeMap::new();
+
+    for (path, entry) in &lock.packages {
+        // workspace members are linked, and their own entry is outside of `node_modules`
+        let Some((_, name)) = path.rsplit_once("node_modules/") else {
+            continue;
+        };
+        let Some(version) = entry.version.as_ref().filter(|_| !entry.link) else {
+            continue;
+        };
+
+        let dependencies = entry
+            .dependencies
+            .keys()
+            .chain(entry.optional_dependencies.keys())
+            .chain(entry.peer_dependencies.keys())
+            .filter_map(|dependency| resolve(&lock.packages, path, dependency))
+            .map(ToString::to_string)
+            .collect();
+
+        packages.insert(
+            path.clone(),
+            Package {
+                name: entry.name.clone().unwrap_or_else(|| name.to_string()),
+                version: version.clone(),
+                origin: entry.resolved.as_deref().and_then(origin_from_url),
+                integrity: entry.integrity.clone(),
+                license: entry
+                    .license
+                    .as_ref()
+                    .and_then(|license| license.as_str())
+                    .map(ToString::to_string),
+                dependencies,
+                scope: Some(entry.scope()),
+            },
+        );
+    }
+
+    Ok(Lock { packages, direct })
+}
+
+/// Convert the tree of a version 1 lockfile into the entries of the later versions.
+fn flatten(
+    packages: &mut BTreeMap<String, Entry>,
+    base: &str,
+    tree: BTreeMap<String, LegacyEntry>,
+) {
+    for (name, entry) in tree {
+        let path = match base {
+            "" => format!("node_modules/{name}"),
+            base => format!("{base}/node_modules/{name}"),
+        };
+
+        flatten(packages, &path, entry.dependencies);
+
+        packages.insert(
+            path,
+            Entry {
+                version: Some(entry.version),
+                resolved: entry.resolved,
+                integrity: entry.integrity,
+                dev: entry.dev,
+                optional: entry.optional,
+                dependencies: entry.requires,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Resolve a dependency of the package at `path`, using the lookup rules of `node_modules`.
+///
+/// Starting with the `node_modules` directory of the package itself, each parent directory is
+/// tried until we reach the root of the project.
+fn resolve<'a>(packages: &'a BTreeMap<String, Entry>, path: &str, name: &str) -> Option<&'a str> {
+    let mut base = path;
+    loop {
+        let candidate = match base {
+            "" => format!("node_modules/{name}"),
+            base => format!("{base}/node_modules/{name}"),
+        };
+        if let Some((key, _)) = packages.get_key_value(&candidate) {
+            return Some(key);
+        }
+        if base.is_empty() {
+            return None;
+        }
+        base = match base.rfind("/node_modules/") {
+            Some(index) => &base[..index],
+            None => "",
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let lock = parse(
+            r#"{
+  "name": "app",
+  "lockfileVersion": 3,
+  "packages": {
+    "": {
+      "name": "app",
+      "dependencies": { "a": "^1" },
+      "devDependencies": { "b": "^1" }
+    },
+    "node_modules/a": {
+      "version": "1.0.0",
+      "resolved": "https://registry.npmjs.org/a/-/a-1.0.0.tgz",
+      "license": "MIT",
+      "dependencies": { "c": "^1" }
+    },
+    "node_modules/b": {
+      "version": "1.0.0",
+      "dev": true,
+      "dependencies": { "c": "^2" }
+    },
+    "node_modules/b/node_modules/c": { "version": "2.0.0", "dev": true },
+    "node_modules/c": { "version": "1.0.0" }
+  }
+}"#,
+            &Default::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            lock.direct,
+            vec![
+                ("node_modules/b".to_string(), Scope::Dev),
+                ("node_modules/a".to_string(), Scope::Runtime)
+            ]
+        );
+        assert_eq!(
+            lock.packages["node_modules/a"].dependencies,
+            vec!["node_modules/c"]
+        );
+        assert_eq!(
+            lock.packages["node_modules/b"].dependencies,
+            vec!["node_modules/b/node_modules/c"]
+        );
+        assert_eq!(lock.packages["node_modules/c"].scope, Some(Scope::Runtime));
+    }
+}
@@ -0,0 +1,278 @@
+//! The `pnpm-lock.yaml` file of pnpm.
+//!
+//! The format changed between the lockfile versions. Version 5 uses keys like `/name/1.0.0`,
+//! version 6 `/name@1.0.0`, and version 9 `name@1.0.0`, moving the dependencies of the packages
+//! into `snapshots`. Peer dependencies get appended to the version, like `1.0.0(react@18.2.0)`.
+
+use super::{origin_from_url, split_name, Lock, Package};
+use crate::enforcer::{Origin, Scope};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLock {
+    /// The projects of a workspace, by their path
+    #[serde(default)]
+    importers: BTreeMap<String, Importer>,
+    /// The project itself, unless it's a workspace, or version 9
+    #[serde(flatten)]
+    root: Importer,
+    #[serde(default)]
+    packages: BTreeMap<String, PnpmPackage>,
+    /// The dependencies of the packages, for version 9
+    #[serde(default)]
+    snapshots: BTreeMap<String, Snapshot>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Importer {
+    #[serde(default)]
+    dependencies: BTreeMap<String, ImporterDependency>,
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, ImporterDependency>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, ImporterDependency>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum ImporterDependency {
+    /// Version 5
+    Version(String),
+    /// Version 6 and later
+    Specified { version: String },
+}
+
+impl ImporterDependency {
+    fn version(&self) -> &str {
+        match self {
+            Self::Version(version) | Self::Specified { version } => version,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PnpmPackage {
+    #[serde(default)]
+    resolution: Resolution,
+    /// Missing in version 9
+    #[serde(default)]
+    dev: Option<bool>,
+    #[serde(default)]
+    optional: bool,
+    #[serde(flatten)]
+    snapshot: Snapshot,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct Resolution {
+    #[serde(default)]
+    integrity: Option<String>,
+    #[serde(default)]
+    tarball: Option<String>,
+    #[serde(default)]
+    repo: Option<String>,
+    #[serde(default)]
+    commit: Option<String>,
+    #[serde(default)]
+    directory: Option<String>,
+}
+
+impl Resolution {
+    fn origin(&self) -> Option<Origin> {
+        if let Some(repo) = &self.repo {
+            Some(Origin::Git {
+                url: repo.clone(),
+                revision: self.commit.clone(),
+            })
+        } else if let Some(directory) = &self.directory {
+            Some(Origin::Path {
+                path: Some(directory.clone()),
+            })
+        } else if let Some(tarball) = &self.tarball {
+            origin_from_url(tarball)
+        } else {
+            Some(Origin::Registry { url: None })
+        }
+    }
+}
+
+/// Parse a package key, into its name and version, dropping the peer dependencies.
+fn parse_key(key: &str) -> Option<(&str, &str)> {
+    let key = key.trim_start_matches('/');
+    let key = key.split('(').next()?;
+
+    match key.rsplit_once('/') {
+        // version 5, where peers are appended using `_`
+        Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+            Some((name, version.split('_').next()?))
+        }
+        // version 6 and later
+        _ => split_name(key),
+    }
+}
+
+/// Resolve the version of a dependency, which might also be an alias or a link.
+fn resolve(name: &str, version: &str) -> Option<String> {
+    if version.starts_with("link:") {
+        return None;
+    }
+
+    // a plain version, otherwise an alias, pointing to a different package
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        let version = version.split('(').next()?.split('_').next()?;
+        Some(format!("{name}@{version}"))
+    } else {
+        let (name, version) = parse_key(version)?;
+        Some(format!("{name}@{version}"))
+    }
+}
+
+pub(super) fn parse(content: &str) -> anyhow::Result<Lock> {
+    let lock: PnpmLock = serde_yaml::from_str(content)?;
+
+    let mut direct = vec![];
+    for importer in lock.importers.values().chain([&lock.root]) {
+        let sections = [
+            (&importer.dependencies, Scope::Runtime),
+            (&importer.optional_dependencies, Scope::Optional),
+            (&importer.dev_dependencies, Scope::Dev),
+        ];
+        for (dependencies, scope) in sections {
+            for (name, dependency) in dependencies {
+                if let Some(key) = resolve(name, dependency.version()) {
+                    direct.push((key, scope));
+                }
+            }
+        }
+    }
+
+    let mut snapshots = BTreeMap::<String, Vec<String>>::new();
+    for (key, snapshot) in lock
+        .snapshots
+        .iter()
+        .chain(lock.packages.iter().map(|(key, p)| (key, &p.snapshot)))
+    {
+        let Some((name, version)) = parse_key(key) else {
+            continue;
+        };
+        snapshots
+            .entry(format!("{name}@{version}"))
+            .or_default()
+            .extend(
+                snapshot
+                    .dependencies
+                    .iter()
+                    .chain(&snapshot.optional_dependencies)
+                    .filter_map(|(name, version)| resolve(name, version)),
+            );
+    }
+
+    let mut packages = BTreeMap::new();
+    for (key, package) in &lock.packages {
+        let Some((name, version)) = parse_key(key) else {
+            continue;
+        };
+        let key = format!("{name}@{version}");
+
+        let scope = match (package.dev, package.optional) {
+            (Some(true), _) => Some(Scope::Dev),
+            (_, true) => Some(Scope::Optional),
+            (Some(false), _) => Some(Scope::Runtime),
+            (None, false) => None,
+        };
+
+        packages.insert(
+            key.clone(),
+            Package {
+                name: name.to_string(),
+                version: version.to_string(),
+                origin: package.resolution.origin(),
+                integrity: package.resolution.integrity.clone(),
+                license: None,
+                dependencies: snapshots.remove(&key).unwrap_or_default(),
+                scope,
+            },
+        );
+    }
+
+    Ok(Lock { packages, direct })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("/react/18.2.0"), Some(("react", "18.2.0")));
+        assert_eq!(
+            parse_key("/@types/react/18.0.0_react@18.2.0"),
+            Some(("@types/react", "18.0.0"))
+        );
+        assert_eq!(parse_key("/react@18.2.0"), Some(("react", "18.2.0")));
+        assert_eq!(
+            parse_key("react-dom@18.2.0(react@18.2.0)"),
+            Some(("react-dom", "18.2.0"))
+        );
+    }
+
+    #[test]
+    fn test_parse_v9() {
+        let lock = parse(
+            r#"
+lockfileVersion: '9.0'
+
+importers:
+  .:
+    dependencies:
+      react-dom:
+        specifier: ^18.2.0
+        version: 18.2.0(react@18.2.0)
+    devDependencies:
+      react:
+        specifier: ^18.2.0
+        version: 18.2.0
+
+packages:
+  react-dom@18.2.0:
+    resolution: {integrity: sha512-6IMTriUmvsjHUjNtEDudZfuDQUoWXVxKHhlEGSk81n4YFS+r/Kl99wXiwlVXtPBtJenozv2P+hxDsw9eA7Xo6g==}
+    peerDependencies:
+      react: ^18.2.0
+  react@18.2.0:
+    resolution: {integrity: sha512-/3IjMdb2L9QbBdWiW5e3P2/npwMBaU9mHCSCUzNln0ZCYbcfTsGbTJrU/kGemdH2IWmB2ioZ+zkxtmq6g09fGQ==}
+
+snapshots:
+  react-dom@18.2.0(react@18.2.0):
+    dependencies:
+      react: 18.2.0
+  react@18.2.0: {}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            lock.direct,
+            vec![
+                ("react-dom@18.2.0".to_string(), Scope::Runtime),
+                ("react@18.2.0".to_string(), Scope::Dev),
+            ]
+        );
+        assert_eq!(
+            lock.packages["react-dom@18.2.0"].dependencies,
+            vec!["react@18.2.0"]
+        );
+        assert_eq!(lock.packages["react@18.2.0"].scope, None);
+    }
+}
@@ -0,0 +1,326 @@
+//! The `yarn.lock` file of yarn.
+//!
+//! Yarn 1 ("classic") uses a format of its own, while later versions ("berry") use YAML. Both
+//! map the requested version ranges (descriptors, like `react@^18.0.0`) to the resolved package.
+
+use super::{origin_from_url, split_name, Lock, Package, PackageJson};
+use crate::enforcer::Origin;
+use std::collections::{BTreeMap, HashMap};
+
+pub(super) fn parse(content: &str, manifest: &PackageJson) -> anyhow::Result<Lock> {
+    let entries = if content.contains("__metadata:") {
+        parse_berry(content)?
+    } else {
+        parse_classic(content)
+    };
+
+    // the packages, by their descriptors
+    let mut descriptors = HashMap::new();
+    for (key, entry) in &entries {
+        for descriptor in &entry.descriptors {
+            descriptors.insert(descriptor.as_str(), key.as_str());
+        }
+    }
+
+    // berry prefixes the ranges of the npm registry with its protocol
+    let lookup = |name: &str, range: &str| {
+        descriptors
+            .get(format!("{name}@{range}").as_str())
+            .or_else(|| descriptors.get(format!("{name}@npm:{range}").as_str()))
+            .map(|key| key.to_string())
+    };
+
+    let direct = manifest
+        .direct()
+        .into_iter()
+        .filter_map(|(name, (range, scope))| Some((lookup(name, range)?, scope)))
+        .collect();
+
+    let packages = entries
+        .iter()
+        .map(|(key, entry)| {
+            let package = Package {
+                dependencies: entry
+                    .dependencies
+                    .iter()
+                    .filter_map(|(name, range)| lookup(name, range))
+                    .collect(),
+                ..entry.package.clone()
+            };
+            (key.clone(), package)
+        })
+        .collect();
+
+    Ok(Lock { packages, direct })
+}
+
+/// An entry of the lockfile
+#[derive(Clone, Debug, Default)]
+struct Entry {
+    /// The version ranges resolving to this entry
+    descriptors: Vec<String>,
+    package: Package,
+    /// The dependencies, by name, with their version range
+    dependencies: Vec<(String, String)>,
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"')
+}
+
+/// Parse the format of yarn 1.
+///
+/// ```text
+/// "@babel/code-frame@^7.0.0", "@babel/code-frame@^7.10.4":
+///   version "7.12.13"
+///   resolved "https://registry.yarnpkg.com/@babel/code-frame/-/code-frame-7.12.13.tgz#dcfc826b"
+///   integrity sha512-HV1Cm0Q3ZrpCR93tkWOYiuYIgLxZXZFVG2VgK+MBWjUqZTundupbfx2aXarXuw5Ko5aMcjtJgbSs4vUGBS5v6g==
+///   dependencies:
+///     "@babel/highlight" "^7.12.13"
+/// ```
+fn parse_classic(content: &str) -> BTreeMap<String, Entry> {
+    let mut entries = BTreeMap::new();
+    let mut current: Option<Entry> = None;
+    // if we are in a section listing dependencies
+    let mut section = false;
+
+    let mut finish = |entry: Option<Entry>| {
+        if let Some(entry) = entry {
+            let key = format!("{}@{}", entry.package.name, entry.package.version);
+            entries.insert(key, entry);
+        }
+    };
+
+    for line in content.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+
+        match indent {
+            0 => {
+                finish(current.take());
+                let descriptors = line
+                    .trim_end_matches(':')
+                    .split(", ")
+                    .map(|descriptor| unquote(descriptor).to_string())
+                    .collect::<Vec<_>>();
+                let name = descriptors
+                    .first()
+                    .and_then(|descriptor| split_name(descriptor))
+                    .map(|(name, _)| name.to_string())
+                    .unwrap_or_default();
+                current = Some(Entry {
+                    descriptors,
+                    package: Package {
+                        name,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+            2 => {
+                let Some(entry) = &mut current else {
+                    continue;
+                };
+                if let Some(name) = line.strip_suffix(':') {
+                    section = matches!(name, "dependencies" | "optionalDependencies");
+                    continue;
+                }
+                section = false;
+                let Some((key, value)) = line.split_once(' ') else {
+                    continue;
+                };
+                let value = unquote(value).to_string();
+                match key {
+                    "version" => entry.package.version = value,
+                    "resolved" => entry.package.origin = origin_from_url(&value),
+                    "integrity" => entry.package.integrity = Some(value),
+                    _ => {}
+                }
+            }
+            _ if section => {
+                let (Some(entry), Some((name, range))) = (&mut current, line.split_once(' '))
+                else {
+                    continue;
+                };
+                entry
+                    .dependencies
+                    .push((unquote(name).to_string(), unquote(range).to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    finish(current);
+
+    entries
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BerryEntry {
+    version: String,
+    /// The resolved package, like `react@npm:18.2.0`
+    resolution: String,
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    peer_dependencies: BTreeMap<String, String>,
+}
+
+/// Parse the YAML format of yarn 2 and later.
+fn parse_berry(content: &str) -> anyhow::Result<BTreeMap<String, Entry>> {
+    let lock: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(content)?;
+
+    let mut entries = BTreeMap::new();
+
+    for (descriptors, value) in lock {
+        if descriptors == "__metadata" {
+            continue;
+        }
+
+        let entry: BerryEntry = serde_yaml::from_value(value)?;
+        let Some((name, reference)) = split_name(&entry.resolution) else {
+            continue;
+        };
+
+        // the project, or another member of the workspace
+        if reference.starts_with("workspace:") {
+            continue;
+        }
+
+        let origin = if reference.starts_with("npm:") {
+            Some(Origin::Registry { url: None })
+        } else if let Some(path) = ["file:", "link:", "portal:"]
+            .iter()
+            .find_map(|protocol| reference.strip_prefix(protocol))
+        {
+            Some(Origin::Path {
+                path: Some(path.to_string()),
+            })
+        } else {
+            origin_from_url(reference)
+        };
+
+        entries.insert(
+            entry.resolution.clone(),
+            Entry {
+                descriptors: descriptors
+                    .split(", ")
+                    .map(|descriptor| descriptor.trim().to_string())
+                    .collect(),
+                package: Package {
+                    name: name.to_string(),
+                    version: entry.version,
+                    origin,
+                    ..Default::default()
+                },
+                dependencies: entry
+                    .dependencies
+                    .into_iter()
+                    .chain(entry.peer_dependencies)
+                    .collect(),
+            },
+        );
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::enforcer::Scope;
+
+    #[test]
+    fn test_parse_classic() {
+        let manifest: PackageJson = serde_json::from_str(
+            r#"{ "dependencies": { "@babel/code-frame": "^7.0.0" }, "devDependencies": { "js-tokens": "^4.0.0" } }"#,
+        )
+        .unwrap();
+
+        let lock = parse(
+            r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+"@babel/code-frame@^7.0.0", "@babel/code-frame@^7.10.4":
+  version "7.12.13"
+  resolved "https://registry.yarnpkg.com/@babel/code-frame/-/code-frame-7.12.13.tgz#dcfc826beef65e75c50e21d3837d7d95798dd658"
+  integrity sha1-qUw4fOr8mW+vbe3Dy/TeajF9DWM=
+  dependencies:
+    js-tokens "^4.0.0"
+
+js-tokens@^4.0.0:
+  version "4.0.0"
+  resolved "https://registry.yarnpkg.com/js-tokens/-/js-tokens-4.0.0.tgz"
+"#,
+            &manifest,
+        )
+        .unwrap();
+
+        assert_eq!(
+            lock.direct,
+            vec![
+                ("@babel/code-frame@7.12.13".to_string(), Scope::Runtime),
+                ("js-tokens@4.0.0".to_string(), Scope::Dev),
+            ]
+        );
+        let package = &lock.packages["@babel/code-frame@7.12.13"];
+        assert_eq!(package.name, "@babel/code-frame");
+        assert_eq!(package.dependencies, vec!["js-tokens@4.0.0"]);
+        assert_eq!(package.origin, Some(Origin::Registry { url: None }));
+    }
+
+    #[test]
+    fn test_parse_berry() {
+        let manifest: PackageJson =
+            serde_json::from_str(r#"{ "dependencies": { "react": "^18.2.0" } }"#).unwrap();
+
+        let lock = parse(
+            r#"
+__metadata:
+  version: 6
+  cacheKey: 8
+
+"app@workspace:.":
+  version: 0.0.0-use.local
+  resolution: "app@workspace:."
+  dependencies:
+    react: ^18.2.0
+  languageName: unknown
+  linkType: soft
+
+"js-tokens@npm:^3.0.0 || ^4.0.0":
+  version: 4.0.0
+  resolution: "js-tokens@npm:4.0.0"
+  languageName: node
+  linkType: hard
+
+"react@npm:^18.2.0":
+  version: 18.2.0
+  resolution: "react@npm:18.2.0"
+  dependencies:
+    loose-envify: ^1.1.0
+    js-tokens: ^3.0.0 || ^4.0.0
+  languageName: node
+  linkType: hard
+"#,
+            &manifest,
+        )
+        .unwrap();
+
+        assert_eq!(
+            lock.direct,
+            vec![("react@npm:18.2.0".to_string(), Scope::Runtime)]
+        );
+        assert_eq!(
+            lock.packages["react@npm:18.2.0"].dependencies,
+            vec!["js-tokens@npm:4.0.0"]
+        );
+        assert!(!lock.packages.contains_key("app@workspace:."));
+    }
+}
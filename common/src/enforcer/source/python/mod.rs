@@ -0,0 +1,357 @@
+//! Dependencies of Python projects, read from the lockfiles of poetry, uv, or pipenv, or from
+//! fully pinned `requirements*.txt` files.
+
+use crate::config::ManifestType;
+use crate::enforcer::{
+    source::{graph::DependencyGraph, Source},
+    Checksum, Dependency, Origin, Scope,
+};
+use crate::highlight::{Range, TextHighlighter, TomlHighlighter};
+use crate::utils::projects::{
+    requirements_files, PIPFILE, PIPFILE_LOCK, POETRY_LOCK_FILE, PYPROJECT_FILE, REQUIREMENTS_FILE,
+    UV_LOCK_FILE,
+};
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use url::Url;
+
+mod pipfile;
+mod poetry;
+mod pyproject;
+mod requirements;
+mod uv;
+
+pub use pipfile::Pipfile;
+pub use pyproject::PyProject;
+
+/// A package of a lockfile, independent of the lockfile format
+#[derive(Clone, Debug, Default)]
+struct Package {
+    /// The normalized name
+    name: String,
+    version: String,
+    origin: Option<Origin>,
+    checksums: Vec<Checksum>,
+    /// The (normalized) names of the packages this package depends on, and their version if the
+    /// lockfile needs to tell them apart
+    dependencies: Vec<(String, Option<String>)>,
+    /// How the package is used, if the lockfile records it
+    scope: Option<Scope>,
+}
+
+/// The direct dependencies, by their normalized name, with their scope
+type Direct = Vec<(String, Scope)>;
+
+/// Normalize a package name, as defined by PEP 503.
+pub fn normalize(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '-' | '_' | '.' => {
+                if !result.ends_with('-') {
+                    result.push('-');
+                }
+            }
+            c => result.push(c.to_ascii_lowercase()),
+        }
+    }
+    result
+}
+
+/// Get the name from a PEP 508 requirement, like `requests[socks]>=2.31`.
+fn requirement_name(requirement: &str) -> &str {
+    let requirement = requirement.trim_start();
+    let end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    &requirement[..end]
+}
+
+fn purl(name: &str, version: &str) -> anyhow::Result<Url> {
+    Ok(Url::parse(&format!(
+        "pkg:pypi/{}@{version}",
+        normalize(name)
+    ))?)
+}
+
+/// Get the (normalized) package name of a PyPI Package URL.
+fn name_from_purl(purl: &Url) -> Option<String> {
+    let (name, _) = purl.path().strip_prefix("pypi/")?.rsplit_once('@')?;
+    Some(name.to_string())
+}
+
+/// Parse a hash, like `sha256:<hex>`.
+fn checksum(hash: &str) -> Option<Checksum> {
+    let (algorithm, value) = hash.split_once(':')?;
+    Some(Checksum {
+        algorithm: algorithm.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// The origin of a package, from the URL of the index it was retrieved from.
+fn registry(url: Option<&str>) -> Origin {
+    match url {
+        None | Some("https://pypi.org/simple" | "https://pypi.org/simple/" | "pypi") => {
+            Origin::Registry { url: None }
+        }
+        Some(url) => Origin::Registry {
+            url: Some(url.to_string()),
+        },
+    }
+}
+
+/// Convert the packages into dependencies, and their graph.
+///
+/// Without the direct dependencies, the graph is unknown.
+fn into_dependencies(
+    packages: Vec<Package>,
+    direct: Option<Direct>,
+    root: Url,
+) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
+    let mut seen = HashSet::new();
+    let mut resolved = vec![];
+    for package in packages {
+        if seen.insert((package.name.clone(), package.version.clone())) {
+            let purl = purl(&package.name, &package.version)?;
+            resolved.push((purl, package));
+        }
+    }
+
+    let Some(direct) = direct.filter(|direct| !direct.is_empty()) else {
+        let dependencies = resolved
+            .into_iter()
+            .map(|(purl, package)| to_dependency(purl, package))
+            .collect();
+        return Ok((dependencies, Default::default()));
+    };
+
+    let mut by_name = HashMap::<_, Vec<_>>::new();
+    for (purl, package) in &resolved {
+        by_name
+            .entry(package.name.as_str())
+            .or_default()
+            .push((package.version.as_str(), purl));
+    }
+    let lookup = |name: &str, version: Option<&str>| {
+        let candidates = by_name.get(name)?;
+        candidates
+            .iter()
+            .find(|(v, _)| Some(*v) == version)
+            .or_else(|| candidates.first())
+            .map(|(_, purl)| (*purl).clone())
+    };
+
+    let mut graph = DependencyGraph::new(HashSet::from([root.clone()]));
+    let mut scopes = HashMap::new();
+
+    for (name, scope) in &direct {
+        if let Some(purl) = lookup(name, None) {
+            graph.add(root.clone(), purl.clone());
+            scopes.insert(purl, *scope);
+        }
+    }
+
+    for (purl, package) in &resolved {
+        for (name, version) in &package.dependencies {
+            if let Some(dependency) = lookup(name, version.as_deref()) {
+                graph.add(purl.clone(), dependency);
+            }
+        }
+    }
+
+    let introductions = graph.introductions();
+    let dependencies = resolved
+        .into_iter()
+        .map(|(purl, package)| {
+            let mut dependency = to_dependency(purl, package);
+            introductions.annotate(&mut dependency);
+
+            // if the lockfile doesn't tell, inherit the scope of the direct dependencies
+            if dependency.scope.is_none() {
+                dependency.scope = dependency
+                    .introduced_by
+                    .iter()
+                    .filter_map(|direct| scopes.get(direct))
+                    .copied()
+                    .min_by_key(|scope| match scope {
+                        Scope::Optional => 1,
+                        Scope::Dev => 2,
+                        _ => 0,
+                    });
+            }
+
+            dependency
+        })
+        .collect();
+
+    Ok((dependencies, graph))
+}
+
+fn to_dependency(purl: Url, package: Package) -> Dependency {
+    let mut dependency = Dependency::new(purl);
+    dependency.scope = package.scope;
+    dependency.origin = package.origin;
+    dependency.checksums = package.checksums;
+    dependency
+}
+
+/// Read a file, which might be missing.
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        result => result.map(Some),
+    }
+}
+
+pub struct PythonSource {
+    root: PathBuf,
+    graph: RwLock<DependencyGraph>,
+}
+
+impl PythonSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            graph: Default::default(),
+        }
+    }
+
+    /// Read the packages from the first lockfile we find, and the direct dependencies
+    fn packages(
+        &self,
+        pyproject: Option<&PyProject>,
+    ) -> anyhow::Result<(Vec<Package>, Option<Direct>)> {
+        let direct = pyproject.map(PyProject::direct);
+
+        if let Some(content) = read_optional(&self.root.join(POETRY_LOCK_FILE))? {
+            return Ok((poetry::parse(&content)?, direct));
+        }
+
+        if let Some(content) = read_optional(&self.root.join(UV_LOCK_FILE))? {
+            return Ok((uv::parse(&content)?, direct));
+        }
+
+        if let Some(content) = read_optional(&self.root.join(PIPFILE_LOCK))? {
+            let direct = match read_optional(&self.root.join(PIPFILE))? {
+                Some(pipfile) => Some(toml::from_str::<Pipfile>(&pipfile)?.direct()),
+                None => None,
+            };
+            return Ok((pipfile::parse(&content)?, direct));
+        }
+
+        let files = requirements_files(&self.root)?;
+        if files.is_empty() {
+            bail!(
+                "Missing lockfile, one of {POETRY_LOCK_FILE}, {UV_LOCK_FILE}, {PIPFILE_LOCK}, or {REQUIREMENTS_FILE} is required"
+            );
+        }
+
+        let mut packages = vec![];
+        let mut direct = None::<Vec<_>>;
+        for file in files {
+            let (p, d) = requirements::parse(&fs::read_to_string(&file)?, &file);
+            packages.extend(p);
+            if let Some(d) = d {
+                direct.get_or_insert_with(Default::default).extend(d);
+            }
+        }
+
+        Ok((packages, direct))
+    }
+
+    /// Find the requirement of a package, in the files declaring the direct dependencies.
+    fn find(&self, name: &str) -> anyhow::Result<Option<(PathBuf, Range)>> {
+        for file in requirements_files(&self.root)? {
+            let content = fs::read_to_string(&file)?;
+            let h = TextHighlighter::new(&content);
+            if let Some(range) = h.find_with(|content| requirements::find(content, name))? {
+                return Ok(Some((file, range)));
+            }
+        }
+
+        let path = self.root.join(PYPROJECT_FILE);
+        if let Some(content) = read_optional(&path)? {
+            let h = TomlHighlighter::<PyProject>::new(&content)?;
+            if let Some(range) = h.find_with(|pyproject| pyproject.find(name))? {
+                return Ok(Some((path, range)));
+            }
+        }
+
+        let path = self.root.join(PIPFILE);
+        if let Some(content) = read_optional(&path)? {
+            let h = TomlHighlighter::<Pipfile>::new(&content)?;
+            if let Some(range) = h.find_with(|pipfile| pipfile.find(name))? {
+                return Ok(Some((path, range)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Source for PythonSource {
+    async fn scan(&self) -> anyhow::Result<Vec<Dependency>> {
+        let pyproject = match read_optional(&self.root.join(PYPROJECT_FILE))? {
+            Some(content) => Some(toml::from_str::<PyProject>(&content)?),
+            None => None,
+        };
+
+        let root = match pyproject.as_ref().and_then(PyProject::name_version) {
+            Some((name, version)) => purl(name, version)?,
+            None => purl("root", "0.0.0")?,
+        };
+
+        let (packages, direct) = self.packages(pyproject.as_ref())?;
+        let (dependencies, graph) = into_dependencies(packages, direct, root)?;
+
+        *self.graph.write().unwrap() = graph;
+
+        Ok(dependencies)
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        self.graph.read().unwrap().clone()
+    }
+
+    fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
+        // highlight the direct dependency, or the one pulling in the transitive dependency
+        let mut candidates = self.graph.read().unwrap().introduced_by(&dependency.purl);
+        if candidates.is_empty() {
+            candidates.push(dependency.purl.clone());
+        }
+
+        for name in candidates.iter().filter_map(name_from_purl) {
+            if let Some((path, range)) = self.find(&name)? {
+                let url =
+                    Url::from_file_path(&path).map_err(|()| anyhow!("Failed to build path URI"))?;
+                return Ok((url, range));
+            }
+        }
+
+        // fall back to the first file declaring dependencies
+        let path = ManifestType::Python.manifest_path(&self.root);
+        let url = Url::from_file_path(path).map_err(|()| anyhow!("Failed to build path URI"))?;
+
+        Ok((url, Range::default()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Flask_SQLAlchemy"), "flask-sqlalchemy");
+        assert_eq!(normalize("zope.interface"), "zope-interface");
+        assert_eq!(normalize("a-_.b"), "a-b");
+        assert_eq!(requirement_name("requests[socks]>=2.31"), "requests");
+    }
+}
@@ -0,0 +1,105 @@
+//! The `Pipfile` and `Pipfile.lock` files of pipenv.
+//!
+//! The lockfile lists all packages, direct or not, without their dependencies. The direct
+//! dependencies are only known from the `Pipfile`.
+
+use super::{checksum, normalize, pyproject, registry, Package};
+use crate::enforcer::{Origin, Scope};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Pipfile {
+    #[serde(default)]
+    pub packages: pyproject::DependencyTable,
+    #[serde(default)]
+    pub dev_packages: pyproject::DependencyTable,
+}
+
+impl Pipfile {
+    /// The direct dependencies, by their normalized name.
+    pub fn direct(&self) -> Vec<(String, Scope)> {
+        pyproject::table(&self.packages, Scope::Runtime)
+            .chain(pyproject::table(&self.dev_packages, Scope::Dev))
+            .collect()
+    }
+
+    /// Find the span of the declaration of a dependency.
+    pub fn find(&self, name: &str) -> Option<Range<usize>> {
+        self.packages
+            .keys()
+            .chain(self.dev_packages.keys())
+            .find(|key| normalize(key.get_ref()) == name)
+            .map(|key| key.span())
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct PipfileLock {
+    #[serde(default)]
+    default: BTreeMap<String, Entry>,
+    #[serde(default)]
+    develop: BTreeMap<String, Entry>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct Entry {
+    /// The pinned version, like `==2.31.0`
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    hashes: Vec<String>,
+    #[serde(default)]
+    index: Option<String>,
+    #[serde(default)]
+    git: Option<String>,
+    #[serde(default)]
+    r#ref: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+}
+
+pub(super) fn parse(content: &str) -> anyhow::Result<Vec<Package>> {
+    let lock: PipfileLock = serde_json::from_str(content)?;
+
+    let sections = [(lock.default, Scope::Runtime), (lock.develop, Scope::Dev)];
+
+    let mut packages = vec![];
+    for (entries, scope) in sections {
+        for (name, entry) in entries {
+            let origin = if let Some(git) = entry.git {
+                Origin::Git {
+                    url: git,
+                    revision: entry.r#ref.clone(),
+                }
+            } else if entry.path.is_some() {
+                // most likely the project itself
+                continue;
+            } else {
+                registry(entry.index.as_deref())
+            };
+
+            let version = match (&entry.version, &entry.r#ref) {
+                (Some(version), _) => version.trim_start_matches('='),
+                (None, Some(reference)) => reference,
+                (None, None) => continue,
+            };
+
+            packages.push(Package {
+                name: normalize(&name),
+                version: version.to_string(),
+                origin: Some(origin),
+                checksums: entry
+                    .hashes
+                    .iter()
+                    .filter_map(|hash| checksum(hash))
+                    .collect(),
+                dependencies: vec![],
+                scope: Some(scope),
+            });
+        }
+    }
+
+    Ok(packages)
+}
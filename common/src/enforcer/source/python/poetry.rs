@@ -0,0 +1,194 @@
+//! The `poetry.lock` file of poetry.
+//!
+//! Older versions keep the hashes of the files in `metadata.files`, by package name, and mark
+//! dev dependencies using `category`. Poetry 1.2 and later list the `groups` instead.
+
+use super::{checksum, normalize, registry, Package};
+use crate::enforcer::{Origin, Scope};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct PoetryLock {
+    #[serde(default)]
+    package: Vec<PoetryPackage>,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct Metadata {
+    #[serde(default)]
+    files: BTreeMap<String, Vec<File>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct File {
+    #[serde(default)]
+    hash: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct PoetryPackage {
+    name: String,
+    version: String,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    groups: Vec<String>,
+    #[serde(default)]
+    optional: bool,
+    #[serde(default)]
+    files: Vec<File>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    source: Option<PoetrySource>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct PoetrySource {
+    r#type: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    reference: Option<String>,
+    #[serde(default)]
+    resolved_reference: Option<String>,
+}
+
+impl PoetryPackage {
+    fn scope(&self) -> Scope {
+        let dev = match &self.category {
+            Some(category) => category == "dev",
+            None => !self.groups.is_empty() && !self.groups.iter().any(|group| group == "main"),
+        };
+        if dev {
+            Scope::Dev
+        } else if self.optional {
+            Scope::Optional
+        } else {
+            Scope::Runtime
+        }
+    }
+
+    fn origin(&self) -> Origin {
+        let Some(source) = &self.source else {
+            return registry(None);
+        };
+        match source.r#type.as_str() {
+            "git" => Origin::Git {
+                url: source.url.clone().unwrap_or_default(),
+                revision: source
+                    .resolved_reference
+                    .clone()
+                    .or_else(|| source.reference.clone()),
+            },
+            "directory" | "file" => Origin::Path {
+                path: source.url.clone(),
+            },
+            _ => registry(source.url.as_deref()),
+        }
+    }
+}
+
+pub(super) fn parse(content: &str) -> anyhow::Result<Vec<Package>> {
+    let mut lock: PoetryLock = toml::from_str(content)?;
+
+    let packages = lock
+        .package
+        .into_iter()
+        .map(|package| {
+            let files = if package.files.is_empty() {
+                lock.metadata
+                    .files
+                    .remove(&package.name)
+                    .unwrap_or_default()
+            } else {
+                package.files.clone()
+            };
+
+            Package {
+                name: normalize(&package.name),
+                version: package.version.clone(),
+                origin: Some(package.origin()),
+                checksums: files
+                    .iter()
+                    .filter_map(|file| checksum(file.hash.as_deref()?))
+                    .collect(),
+                dependencies: package
+                    .dependencies
+                    .keys()
+                    .map(|name| (normalize(name), None))
+                    .collect(),
+                scope: Some(package.scope()),
+            }
+        })
+        .collect();
+
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let packages = parse(
+            r#"
+[[package]]
+name = "Flask"
+version = "3.0.0"
+optional = false
+groups = ["main"]
+files = [
+    {file = "flask-3.0.0-py3-none-any.whl", hash = "sha256:21128f47e4e3b9d597a3e8521a329bf56909b690fcc3fa3e477725aa81367638"},
+]
+
+[package.dependencies]
+Werkzeug = ">=3.0.0"
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+category = "dev"
+optional = false
+
+[[package]]
+name = "mylib"
+version = "0.1.0"
+groups = ["main"]
+
+[package.source]
+type = "git"
+url = "https://github.com/example/mylib.git"
+reference = "main"
+resolved-reference = "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+
+[metadata.files]
+pytest = [
+    {file = "pytest-8.0.0.tar.gz", hash = "sha256:249b1b0864530ba251b7438274c4d251c58d868edaaec8762893ad4a0d71c36c"},
+]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(packages[0].name, "flask");
+        assert_eq!(packages[0].scope, Some(Scope::Runtime));
+        assert_eq!(
+            packages[0].dependencies,
+            vec![("werkzeug".to_string(), None)]
+        );
+        assert_eq!(packages[0].checksums.len(), 1);
+        assert_eq!(packages[1].scope, Some(Scope::Dev));
+        assert_eq!(packages[1].checksums[0].algorithm, "sha256");
+        assert_eq!(
+            packages[2].origin,
+            Some(Origin::Git {
+                url: "https://github.com/example/mylib.git".to_string(),
+                revision: Some("4b825dc642cb6eb9a060e54bf8d69288fbee4904".to_string()),
+            })
+        );
+    }
+}
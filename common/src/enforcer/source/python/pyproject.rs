@@ -0,0 +1,211 @@
+//! The `pyproject.toml` file, declaring the direct dependencies.
+//!
+//! Dependencies are either declared as PEP 508 requirements in the `project` table (and the
+//! `dependency-groups` of PEP 735), or in the tables of poetry, by their name.
+
+use super::{normalize, requirement_name};
+use crate::enforcer::Scope;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use toml::Spanned;
+
+/// Dependencies declared by name, like the tables of poetry
+pub type DependencyTable = BTreeMap<Spanned<String>, toml::Value>;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PyProject {
+    #[serde(default)]
+    pub project: Option<Project>,
+    #[serde(default)]
+    pub tool: Tool,
+    /// Groups of requirements, might also include other groups
+    #[serde(default)]
+    pub dependency_groups: BTreeMap<String, Vec<Spanned<toml::Value>>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Project {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<Spanned<String>>,
+    #[serde(default)]
+    pub optional_dependencies: BTreeMap<String, Vec<Spanned<String>>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct Tool {
+    #[serde(default)]
+    pub poetry: Option<Poetry>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Poetry {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub dependencies: DependencyTable,
+    /// The dev dependencies, before poetry 1.2 introduced groups
+    #[serde(default)]
+    pub dev_dependencies: DependencyTable,
+    #[serde(default)]
+    pub group: BTreeMap<String, Group>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct Group {
+    #[serde(default)]
+    pub dependencies: DependencyTable,
+}
+
+impl PyProject {
+    /// The name and version of the project.
+    pub fn name_version(&self) -> Option<(&str, &str)> {
+        let project = self
+            .project
+            .as_ref()
+            .and_then(|project| Some((project.name.as_deref()?, project.version.as_deref()?)));
+        project.or_else(|| {
+            let poetry = self.tool.poetry.as_ref()?;
+            Some((poetry.name.as_deref()?, poetry.version.as_deref()?))
+        })
+    }
+
+    /// The direct dependencies, by their normalized name.
+    pub fn direct(&self) -> Vec<(String, Scope)> {
+        let mut result = vec![];
+
+        if let Some(project) = &self.project {
+            result.extend(requirements(&project.dependencies, Scope::Runtime));
+            for dependencies in project.optional_dependencies.values() {
+                result.extend(requirements(dependencies, Scope::Optional));
+            }
+        }
+
+        for dependencies in self.dependency_groups.values() {
+            let dependencies = dependencies
+                .iter()
+                .filter_map(|value| value.get_ref().as_str())
+                .map(|requirement| (normalize(requirement_name(requirement)), Scope::Dev));
+            result.extend(dependencies);
+        }
+
+        if let Some(poetry) = &self.tool.poetry {
+            result.extend(table(&poetry.dependencies, Scope::Runtime));
+            result.extend(table(&poetry.dev_dependencies, Scope::Dev));
+            for (name, group) in &poetry.group {
+                let scope = match name.as_str() {
+                    "main" => Scope::Runtime,
+                    _ => Scope::Dev,
+                };
+                result.extend(table(&group.dependencies, scope));
+            }
+        }
+
+        result
+    }
+
+    /// Find the span of the declaration of a dependency.
+    pub fn find(&self, name: &str) -> Option<Range<usize>> {
+        let matches = |requirement: &str| normalize(requirement_name(requirement)) == name;
+
+        if let Some(project) = &self.project {
+            let found = project
+                .dependencies
+                .iter()
+                .chain(project.optional_dependencies.values().flatten())
+                .find(|requirement| matches(requirement.get_ref()));
+            if let Some(requirement) = found {
+                return Some(requirement.span());
+            }
+        }
+
+        let found = self.dependency_groups.values().flatten().find(
+            |value| matches!(value.get_ref().as_str(), Some(requirement) if matches(requirement)),
+        );
+        if let Some(value) = found {
+            return Some(value.span());
+        }
+
+        let poetry = self.tool.poetry.as_ref()?;
+        let tables = [&poetry.dependencies, &poetry.dev_dependencies]
+            .into_iter()
+            .chain(poetry.group.values().map(|group| &group.dependencies));
+        for table in tables {
+            if let Some(key) = table.keys().find(|key| normalize(key.get_ref()) == name) {
+                return Some(key.span());
+            }
+        }
+
+        None
+    }
+}
+
+fn requirements(
+    requirements: &[Spanned<String>],
+    scope: Scope,
+) -> impl Iterator<Item = (String, Scope)> + '_ {
+    requirements
+        .iter()
+        .map(move |requirement| (normalize(requirement_name(requirement.get_ref())), scope))
+}
+
+/// The dependencies of a table, skipping the constraint on the version of Python itself.
+pub(super) fn table(
+    table: &DependencyTable,
+    scope: Scope,
+) -> impl Iterator<Item = (String, Scope)> + '_ {
+    table
+        .keys()
+        .filter(|name| name.get_ref() != "python")
+        .map(move |name| (normalize(name.get_ref()), scope))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_direct() {
+        let content = r#"
+[project]
+name = "app"
+version = "1.0.0"
+dependencies = ["Requests[socks]>=2.31", "click"]
+
+[project.optional-dependencies]
+yaml = ["PyYAML"]
+
+[dependency-groups]
+test = ["pytest>=8", { include-group = "lint" }]
+
+[tool.poetry.group.dev.dependencies]
+black = "^24"
+"#;
+        let pyproject: PyProject = toml::from_str(content).unwrap();
+
+        assert_eq!(pyproject.name_version(), Some(("app", "1.0.0")));
+        assert_eq!(
+            pyproject.direct(),
+            vec![
+                ("requests".to_string(), Scope::Runtime),
+                ("click".to_string(), Scope::Runtime),
+                ("pyyaml".to_string(), Scope::Optional),
+                ("pytest".to_string(), Scope::Dev),
+                ("black".to_string(), Scope::Dev),
+            ]
+        );
+
+        let span = pyproject.find("requests").unwrap();
+        assert_eq!(&content[span], r#""Requests[socks]>=2.31""#);
+        let span = pyproject.find("black").unwrap();
+        assert_eq!(&content[span], "black");
+    }
+}
@@ -0,0 +1,249 @@
+//! The `requirements*.txt` files of pip.
+//!
+//! Only fully pinned requirements (`name==version`) are considered, others can't be evaluated
+//! without resolving them. Files compiled by `pip-compile` annotate each requirement with the
+//! packages pulling it in (`# via`), which gives us the graph.
+
+use super::{checksum, normalize, requirement_name, Direct, Package};
+use crate::enforcer::Scope;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+/// The lines of a file, joining continuation lines
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut result = vec![];
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        let (text, continued) = match line.strip_suffix('\\') {
+            Some(text) => (text, true),
+            None => (line, false),
+        };
+
+        let joined = current.get_or_insert_with(String::new);
+        joined.push_str(text);
+        joined.push(' ');
+
+        if !continued {
+            result.extend(current.take());
+        }
+    }
+
+    result.extend(current);
+    result
+}
+
+/// Parse a pinned requirement, like `requests[socks]==2.31.0 ; python_version > "3.8"`
+fn parse_requirement(line: &str) -> Option<(&str, &str)> {
+    let line = line.split(';').next()?.trim();
+    let name = requirement_name(line);
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut rest = line[name.len()..].trim_start();
+    if rest.starts_with('[') {
+        rest = rest[rest.find(']')? + 1..].trim_start();
+    }
+
+    let version = rest
+        .strip_prefix("===")
+        .or_else(|| rest.strip_prefix("=="))?
+        .trim_start();
+    let version = version.split_whitespace().next()?;
+
+    // a wildcard is still a range
+    if version.contains('*') || version.contains(',') {
+        return None;
+    }
+
+    Some((name, version))
+}
+
+/// Parse a requirements file, returning its packages, and the direct dependencies if the file
+/// records them.
+pub(super) fn parse(content: &str, path: &Path) -> (Vec<Package>, Option<Direct>) {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let scope = if stem.contains("dev") || stem.contains("test") {
+        Scope::Dev
+    } else {
+        Scope::Runtime
+    };
+
+    let mut packages = Vec::<Package>::new();
+    // the packages pulling in a package, by its index
+    let mut via = BTreeMap::<usize, Vec<String>>::new();
+    let mut in_via = false;
+
+    for line in logical_lines(content) {
+        let trimmed = line.trim();
+
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            let comment = comment.trim();
+            let indented = line.starts_with(char::is_whitespace);
+            if packages.is_empty() || !indented {
+                in_via = false;
+                continue;
+            }
+            let index = packages.len() - 1;
+            if comment == "via" {
+                in_via = true;
+            } else if let Some(source) = comment.strip_prefix("via ") {
+                in_via = false;
+                via.entry(index)
+                    .or_default()
+                    .push(source.trim().to_string());
+            } else if in_via {
+                via.entry(index).or_default().push(comment.to_string());
+            }
+            continue;
+        }
+
+        in_via = false;
+
+        // options, like `--index-url`, or `-r other.txt`
+        if trimmed.is_empty() || trimmed.starts_with('-') {
+            continue;
+        }
+
+        let requirement = trimmed.split(" #").next().unwrap_or_default();
+        let Some((name, version)) = parse_requirement(requirement) else {
+            log::debug!("Skipping requirement which isn't pinned: {requirement}");
+            continue;
+        };
+
+        let checksums = requirement
+            .split_whitespace()
+            .filter_map(|option| option.strip_prefix("--hash="))
+            .filter_map(checksum)
+            .collect();
+
+        packages.push(Package {
+            name: normalize(name),
+            version: version.to_string(),
+            origin: None,
+            checksums,
+            dependencies: vec![],
+            scope: Some(scope),
+        });
+    }
+
+    if via.is_empty() {
+        return (packages, None);
+    }
+
+    let mut direct = vec![];
+    let mut edges = vec![];
+    for (index, sources) in via {
+        let name = packages[index].name.clone();
+        for source in sources {
+            // another requirements file, or a project, like `app (pyproject.toml)`
+            if source.starts_with('-') || source.contains('(') {
+                direct.push((name.clone(), scope));
+            } else {
+                edges.push((normalize(&source), name.clone()));
+            }
+        }
+    }
+
+    for (dependent, dependency) in edges {
+        if let Some(package) = packages.iter_mut().find(|p| p.name == dependent) {
+            package.dependencies.push((dependency, None));
+        }
+    }
+
+    (packages, Some(direct))
+}
+
+/// Find the span of the requirement of a package.
+pub(super) fn find(content: &str, name: &str) -> Option<Range<usize>> {
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(['#', '-']) {
+            continue;
+        }
+
+        let requirement = requirement_name(trimmed);
+        if !requirement.is_empty() && normalize(requirement) == name {
+            let text = trimmed.trim_end().trim_end_matches('\\').trim_end();
+            let begin = start + (line.len() - trimmed.len());
+            return Some(begin..begin + text.len());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONTENT: &str = r#"#
+# This file is autogenerated by pip-compile with Python 3.12
+#
+--index-url https://pypi.org/simple
+
+certifi==2024.2.2 \
+    --hash=sha256:0569859f95fc761b18b45ef421b1290a0f65f147e92a1e5eb3e635f9a5e4e66f \
+    --hash=sha256:dc383c07b76109f368f6106eee2b593b04a011ea4d55f652c6ca24a754d1cdd1
+    # via requests
+Requests[socks]==2.31.0 ; python_version >= "3.8"
+    # via
+    #   -r requirements.in
+    #   other
+other==1.0
+    # via -r requirements.in
+unpinned>=1.0
+"#;
+
+    #[test]
+    fn test_parse() {
+        let (packages, direct) = parse(CONTENT, Path::new("requirements.txt"));
+
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].name, "certifi");
+        assert_eq!(packages[0].version, "2024.2.2");
+        assert_eq!(packages[0].checksums.len(), 2);
+        assert_eq!(packages[1].name, "requests");
+        assert_eq!(packages[1].version, "2.31.0");
+        assert_eq!(
+            packages[1].dependencies,
+            vec![("certifi".to_string(), None)]
+        );
+        assert_eq!(
+            packages[2].dependencies,
+            vec![("requests".to_string(), None)]
+        );
+        assert_eq!(
+            direct,
+            Some(vec![
+                ("requests".to_string(), Scope::Runtime),
+                ("other".to_string(), Scope::Runtime),
+            ])
+        );
+
+        let (packages, direct) = parse("pytest==8.0.0\n", Path::new("requirements-dev.txt"));
+        assert_eq!(packages[0].scope, Some(Scope::Dev));
+        assert_eq!(direct, None);
+    }
+
+    #[test]
+    fn test_find() {
+        let span = find(CONTENT, "requests").unwrap();
+        assert_eq!(
+            &CONTENT[span],
+            r#"Requests[socks]==2.31.0 ; python_version >= "3.8""#
+        );
+        let span = find(CONTENT, "certifi").unwrap();
+        assert_eq!(&CONTENT[span], "certifi==2024.2.2");
+    }
+}
@@ -0,0 +1,122 @@
+//! The `uv.lock` file of uv.
+//!
+//! Dependencies only carry a version when the lockfile contains more than one version of the
+//! package. The project itself, and the members of a workspace, are editable or virtual packages.
+
+use super::{checksum, normalize, registry, Package};
+use crate::enforcer::Origin;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UvLock {
+    #[serde(default)]
+    package: Vec<UvPackage>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct UvPackage {
+    name: String,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    source: UvSource,
+    #[serde(default)]
+    dependencies: Vec<UvDependency>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, Vec<UvDependency>>,
+    #[serde(default)]
+    sdist: Option<Artifact>,
+    #[serde(default)]
+    wheels: Vec<Artifact>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UvSource {
+    #[serde(default)]
+    registry: Option<String>,
+    #[serde(default)]
+    git: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    directory: Option<String>,
+    #[serde(default)]
+    editable: Option<String>,
+    #[serde(default)]
+    r#virtual: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct UvDependency {
+    name: String,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+struct Artifact {
+    #[serde(default)]
+    hash: Option<String>,
+}
+
+impl UvSource {
+    fn origin(&self) -> Origin {
+        if let Some(git) = &self.git {
+            // like `https://github.com/example/lib?rev=main#<commit>`
+            let (url, revision) = match git.split_once('#') {
+                Some((url, revision)) => (url, Some(revision.to_string())),
+                None => (git.as_str(), None),
+            };
+            let url = url.split('?').next().unwrap_or(url);
+            Origin::Git {
+                url: url.to_string(),
+                revision,
+            }
+        } else if let Some(path) = self.path.as_ref().or(self.directory.as_ref()) {
+            Origin::Path {
+                path: Some(path.clone()),
+            }
+        } else {
+            registry(self.registry.as_deref().or(self.url.as_deref()))
+        }
+    }
+}
+
+pub(super) fn parse(content: &str) -> anyhow::Result<Vec<Package>> {
+    let lock: UvLock = toml::from_str(content)?;
+
+    let packages = lock
+        .package
+        .into_iter()
+        // skip the project, and the members of its workspace
+        .filter(|package| package.source.editable.is_none() && package.source.r#virtual.is_none())
+        .filter_map(|package| {
+            let checksums = package
+                .sdist
+                .iter()
+                .chain(&package.wheels)
+                .filter_map(|artifact| checksum(artifact.hash.as_deref()?))
+                .collect();
+            let dependencies = package
+                .dependencies
+                .iter()
+                .chain(package.optional_dependencies.values().flatten())
+                .map(|dependency| (normalize(&dependency.name), dependency.version.clone()))
+                .collect();
+
+            Some(Package {
+                name: normalize(&package.name),
+                version: package.version?,
+                origin: Some(package.source.origin()),
+                checksums,
+                dependencies,
+                scope: None,
+            })
+        })
+        .collect();
+
+    Ok(packages)
+}
@@ -0,0 +1,65 @@
+use crate::{
+    enforcer::{
+        source::sbom::{Generator, Output, Type},
+        Dependency,
+    },
+    highlight::{Range, TextHighlighter},
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use std::{fs, path::PathBuf};
+use url::Url;
+
+/// A generator reading an existing SBOM, like one created by a CI pipeline.
+pub struct FileGenerator {
+    path: PathBuf,
+}
+
+impl FileGenerator {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl Generator for FileGenerator {
+    async fn generate(&self) -> anyhow::Result<Output> {
+        let content = fs::read(&self.path)?;
+        let r#type = Type::detect(&content)?;
+
+        Ok(Output { r#type, content })
+    }
+
+    fn highlight(&self, dependency: &Dependency, _: &[Dependency]) -> anyhow::Result<(Url, Range)> {
+        let content = fs::read_to_string(&self.path)?;
+        let h = TextHighlighter::new(&content);
+        let url =
+            Url::from_file_path(&self.path).map_err(|()| anyhow!("Failed to create file URL"))?;
+
+        // the SBOM might not encode the Package URL the same way we do
+        let purl = dependency.purl.as_str();
+        let candidates = [purl.to_string(), purl.replace("%40", "@")];
+
+        let position = candidates
+            .iter()
+            .find_map(|candidate| {
+                h.find_with(|content| find_purl(content, candidate))
+                    .transpose()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok((url, position))
+    }
+}
+
+/// Find a Package URL, which must not be the prefix of another one, like one with a longer version.
+fn find_purl(content: &str, purl: &str) -> Option<std::ops::Range<usize>> {
+    content
+        .match_indices(purl)
+        .map(|(start, _)| start..start + purl.len())
+        .find(|span| {
+            let next = content[span.end..].chars().next();
+            !matches!(next, Some(c) if c.is_alphanumeric() || matches!(c, '.' | '-' | '+'))
+        })
+}
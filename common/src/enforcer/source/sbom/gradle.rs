@@ -0,0 +1,173 @@
+use crate::{
+    enforcer::{
+        source::{
+            gradle,
+            sbom::{Generator, Output, Type},
+        },
+        Dependency,
+    },
+    highlight::Range,
+    utils::{
+        process::{self, Execution},
+        projects::{
+            GRADLE_BUILDSCRIPT_LOCK_FILE, GRADLE_DIR, GRADLE_FILE, GRADLE_KTS_FILE,
+            GRADLE_LOCK_FILE, GRADLE_PROPERTIES_FILE, GRADLE_SETTINGS_FILE,
+            GRADLE_SETTINGS_KTS_FILE,
+        },
+    },
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use url::Url;
+
+/// An init script, applying the CycloneDX plugin to the root project, without changing the build.
+const INIT_SCRIPT: &str = r#"
+initscript {
+    repositories {
+        gradlePluginPortal()
+    }
+    dependencies {
+        classpath "org.cyclonedx:cyclonedx-gradle-plugin:1.7.4"
+    }
+}
+
+rootProject {
+    apply plugin: org.cyclonedx.gradle.CycloneDxPlugin
+
+    cyclonedxBom {
+        outputFormat = "json"
+        schemaVersion = "1.4"
+        outputName = "bom"
+        includeBomSerialNumber = false
+    }
+}
+"#;
+
+/// Files of the build, in the project and its subprojects
+const BUILD_FILES: &[&str] = &[
+    GRADLE_FILE,
+    GRADLE_KTS_FILE,
+    GRADLE_SETTINGS_FILE,
+    GRADLE_SETTINGS_KTS_FILE,
+    GRADLE_PROPERTIES_FILE,
+    GRADLE_LOCK_FILE,
+    GRADLE_BUILDSCRIPT_LOCK_FILE,
+];
+
+/// Collect the build files of a directory, and its subdirectories.
+fn build_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+        if path.is_dir() {
+            // skip outputs, caches, and hidden directories
+            if !matches!(name, "build" | "node_modules") && !name.starts_with('.') {
+                build_files(&path, files)?;
+            }
+        } else if BUILD_FILES.contains(&name) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+pub struct GradleGenerator {
+    root: PathBuf,
+    execution: Execution,
+}
+
+#[async_trait]
+impl Generator for GradleGenerator {
+    async fn generate(&self) -> anyhow::Result<Output> {
+        let content = self.run().await?;
+        // the schema version depends on the version of the plugin
+        let r#type = Type::detect(&content)?;
+
+        Ok(Output { r#type, content })
+    }
+
+    fn highlight(
+        &self,
+        _: &Dependency,
+        introduced_by: &[Dependency],
+    ) -> anyhow::Result<(Url, Range)> {
+        gradle::highlight(&self.root, introduced_by)
+    }
+
+    fn inputs(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut inputs = vec![];
+        build_files(&self.root, &mut inputs)?;
+
+        // the version catalog, and the version of the wrapper
+        let gradle = self.root.join(GRADLE_DIR);
+        if gradle.is_dir() {
+            inputs.push(gradle);
+        }
+
+        Ok(inputs)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+const GRADLE_WRAPPER: &str = "gradlew";
+#[cfg(target_os = "windows")]
+const GRADLE_WRAPPER: &str = "gradlew.bat";
+
+impl GradleGenerator {
+    pub fn new(root: impl Into<PathBuf>, execution: Execution) -> Self {
+        Self {
+            root: root.into(),
+            execution,
+        }
+    }
+
+    fn find_gradle(&self) -> anyhow::Result<PathBuf> {
+        // unlike Maven, the wrapper is the common way to run a build
+        let gradlew = self.root.join(GRADLE_WRAPPER);
+        log::debug!("Checking existence: {}", gradlew.display());
+        if gradlew.exists() {
+            return Ok(gradlew);
+        }
+
+        if let Ok(gradle) = which::which("gradle") {
+            return Ok(gradle);
+        }
+
+        Err(anyhow!("could not find 'gradle' command"))
+    }
+
+    async fn run(&self) -> anyhow::Result<Vec<u8>> {
+        let gradle = self.find_gradle()?;
+
+        let init_script =
+            std::env::temp_dir().join(format!("senf-cyclonedx-{}.gradle", std::process::id()));
+        fs::write(&init_script, INIT_SCRIPT)?;
+
+        let mut command = Command::new(gradle);
+        command
+            .current_dir(&self.root)
+            .arg("--init-script")
+            .arg(&init_script)
+            .args(["--console=plain", "cyclonedxBom"]);
+
+        let result = process::run(command, &self.execution).await;
+
+        let _ = fs::remove_file(&init_script);
+        result?;
+
+        Ok(fs::read(
+            self.root.join("build").join("reports").join("bom.json"),
+        )?)
+    }
+}
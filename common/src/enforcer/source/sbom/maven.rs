@@ -1,61 +1,82 @@
-use crate::utils::projects::MAVEN_FILE;
 use crate::{
     enforcer::{
-        source::sbom::{CycloneDXFormat, CycloneDXVersion, Generator, Output, Type},
-        Dependency,
+        source::{
+            maven,
+            sbom::{Generator, Output, Type},
+            Module,
+        },
+        Dependency, Scope,
+    },
+    highlight::Range,
+    utils::{
+        process::{self, Execution},
+        projects::{MAVEN_CONFIG_DIR, MAVEN_FILE},
     },
-    highlight::{Highlighter, Range},
 };
-use anyhow::{anyhow, bail};
+use anyhow::anyhow;
 use async_trait::async_trait;
+use cyclonedx_bom::models::component;
 use std::{fs, path::PathBuf, process::Command};
 use url::Url;
 
 pub struct MavenGenerator {
     root: PathBuf,
+    execution: Execution,
 }
 
 #[async_trait]
 impl Generator for MavenGenerator {
     async fn generate(&self) -> anyhow::Result<Output> {
-        Ok(Output {
-            r#type: Type::CycloneDX {
-                format: CycloneDXFormat::Json,
-                version: CycloneDXVersion::V1_3,
-            },
-            content: self.run()?,
-        })
+        let content = self.run().await?;
+        // the schema version depends on the version of the plugin
+        let r#type = Type::detect(&content)?;
+
+        Ok(Output { r#type, content })
+    }
+
+    fn highlight(
+        &self,
+        _: &Dependency,
+        introduced_by: &[Dependency],
+    ) -> anyhow::Result<(Url, Range)> {
+        maven::highlight(&self.root.join(MAVEN_FILE), introduced_by)
+    }
+
+    fn modules(&self) -> anyhow::Result<Vec<Module>> {
+        maven::modules(&self.root)
+    }
+
+    fn highlight_module(
+        &self,
+        module: &Module,
+        _: &Dependency,
+        introduced_by: &[Dependency],
+    ) -> anyhow::Result<(Url, Range)> {
+        maven::highlight(&module.manifest, introduced_by)
+    }
+
+    fn scope(&self, scope: &component::Scope) -> Option<Scope> {
+        // this follows the mapping of the CycloneDX Maven plugin
+        match scope {
+            component::Scope::Required => Some(Scope::Runtime),
+            component::Scope::Optional => Some(Scope::Provided),
+            component::Scope::Excluded => Some(Scope::Test),
+            _ => None,
+        }
     }
 
-    fn highlight(&self, _: &Dependency) -> anyhow::Result<(Url, Range)> {
-        let content = fs::read_to_string(self.root.join(MAVEN_FILE))?;
-        let h = Highlighter::new(&content)?;
-        let url = Url::from_file_path(self.root.join(MAVEN_FILE))
-            .map_err(|()| anyhow!("Failed to create file URL"))?;
-
-        // TODO: find actual dependency
-        // TODO: find parent of transient dependency
-
-        let position = h
-            // find the main dependencies section
-            .find_with(|doc| {
-                doc.root_element()
-                    .children()
-                    .find(|p| p.tag_name().name() == "dependencies")
-            })
-            // then fall back to dependencies section
-            .or_else(|_| {
-                h.find_with(|doc| {
-                    doc.root_element()
-                        .children()
-                        .find(|p| p.tag_name().name() == "dependencyManagement")
-                        .and_then(|d| d.children().find(|p| p.tag_name().name() == "dependencies"))
-                })
-            })?
-            // or the full document
-            .unwrap_or_else(|| h.full_range());
-
-        Ok((url, position))
+    fn inputs(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut inputs = maven::module_manifests(&self.root)?;
+        // parents outside of the build, referenced by their relative path
+        inputs.extend(maven::parent_manifests(&inputs));
+
+        // like the extensions, or the version of the wrapper
+        let mvn = self.root.join(MAVEN_CONFIG_DIR);
+        if mvn.is_dir() {
+            inputs.push(mvn);
+        }
+
+        Ok(inputs)
     }
 }
 
@@ -65,8 +86,11 @@ const MVN_WRAPPER: &str = "mvnw";
 const MVN_WRAPPER: &str = "mvnw.cmd";
 
 impl MavenGenerator {
-    pub fn new(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+    pub fn new(root: impl Into<PathBuf>, execution: Execution) -> Self {
+        Self {
+            root: root.into(),
+            execution,
+        }
     }
 
     fn find_mvn(&self) -> anyhow::Result<PathBuf> {
@@ -83,34 +107,48 @@ impl MavenGenerator {
         Err(anyhow!("could not find 'mvn' command"))
     }
 
-    fn run(&self) -> anyhow::Result<Vec<u8>> {
+    async fn run(&self) -> anyhow::Result<Vec<u8>> {
         let mvn = self.find_mvn()?;
 
-        let output = Command::new(mvn)
-            .current_dir(&self.root)
-            .args([
-                "org.cyclonedx:cyclonedx-maven-plugin:2.7.5:makeAggregateBom",
-                "-Dcyclonedx.skipAttach=true",
-                "-DoutputFormat=json",
-                "-DschemaVersion=1.3",
-                "-Dcyclonedx.verbose=false",
-            ])
-            .output()?;
-
-        log::info!("Status: {}", output.status);
-        log::info!(
-            "Output (stdout):\n{}",
-            String::from_utf8_lossy(&output.stdout)
-        );
-        log::info!(
-            "Output (stderr):\n{}",
-            String::from_utf8_lossy(&output.stderr)
-        );
+        let mut command = Command::new(mvn);
+        command.current_dir(&self.root).args([
+            // no colors, and no download progress, as the output ends up in diagnostics
+            "--batch-mode",
+            "--no-transfer-progress",
+            "org.cyclonedx:cyclonedx-maven-plugin:2.7.10:makeAggregateBom",
+            "-Dcyclonedx.skipAttach=true",
+            "-DoutputFormat=json",
+            "-DschemaVersion=1.5",
+            "-Dcyclonedx.verbose=false",
+        ]);
 
-        if !output.status.success() {
-            bail!("Failed to run Maven SBOM generator");
-        }
+        process::run(command, &self.execution).await?;
 
         Ok(fs::read(self.root.join("target").join("bom.json"))?)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_inputs() {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test-data/maven/nested")
+            .canonicalize()
+            .unwrap();
+        let generator = MavenGenerator::new(test_data.join("build"), Execution::default());
+
+        // a module with its own file name, and a parent outside of the build
+        assert_eq!(
+            generator.inputs().unwrap(),
+            vec![
+                test_data.join("build/pom.xml"),
+                test_data.join("build/lib/lib-pom.xml"),
+                test_data.join("parent/pom.xml"),
+            ]
+        );
+    }
+}
@@ -1,10 +1,25 @@
-use crate::enforcer::{source::Source, Dependency};
+use crate::enforcer::{
+    cache::disk::digest_paths,
+    source::{self, graph::DependencyGraph, Module, Source},
+    Checksum, Dependency, Scope,
+};
 use crate::highlight::Range;
+use anyhow::bail;
 use async_trait::async_trait;
+use cyclonedx_bom::models::{
+    component,
+    license::{LicenseChoice, LicenseIdentifier},
+};
 use cyclonedx_bom::prelude::{Bom, Component};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 use url::Url;
 
+pub mod file;
+pub mod gradle;
 pub mod maven;
+pub mod spdx;
 
 #[derive(Clone, Copy, Debug)]
 pub enum CycloneDXFormat {
@@ -15,6 +30,26 @@ pub enum CycloneDXFormat {
 #[derive(Clone, Copy, Debug)]
 pub enum CycloneDXVersion {
     V1_3,
+    V1_4,
+    V1_5,
+}
+
+impl CycloneDXVersion {
+    /// Parse a spec version, like `1.3`.
+    pub fn parse(version: &str) -> anyhow::Result<Self> {
+        match version {
+            "1.3" => Ok(Self::V1_3),
+            "1.4" => Ok(Self::V1_4),
+            "1.5" => Ok(Self::V1_5),
+            _ => bail!("Unsupported CycloneDX version: {version}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum SpdxFormat {
+    Json,
+    TagValue,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -23,6 +58,74 @@ pub enum Type {
         format: CycloneDXFormat,
         version: CycloneDXVersion,
     },
+    Spdx {
+        format: SpdxFormat,
+    },
+}
+
+/// Check the version of an SPDX document, like `SPDX-2.3`.
+fn check_spdx_version(version: &str) -> anyhow::Result<()> {
+    let version = version.trim();
+    match version.strip_prefix("SPDX-2.") {
+        Some(_) => Ok(()),
+        None => bail!("Unsupported SPDX version: {version}"),
+    }
+}
+
+impl Type {
+    /// Detect the type of an SBOM from its content.
+    pub fn detect(content: &[u8]) -> anyhow::Result<Self> {
+        let content = std::str::from_utf8(content)?
+            .trim_start_matches('\u{feff}')
+            .trim_start();
+
+        if content.starts_with('{') {
+            #[derive(serde::Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct Header {
+                bom_format: Option<String>,
+                spec_version: Option<String>,
+                spdx_version: Option<String>,
+            }
+
+            let header: Header = serde_json::from_str(content)?;
+            if let Some(version) = header.spdx_version {
+                check_spdx_version(&version)?;
+                return Ok(Type::Spdx {
+                    format: SpdxFormat::Json,
+                });
+            }
+
+            match (header.bom_format.as_deref(), header.spec_version) {
+                (Some("CycloneDX"), Some(version)) => Ok(Type::CycloneDX {
+                    format: CycloneDXFormat::Json,
+                    version: CycloneDXVersion::parse(&version)?,
+                }),
+                _ => bail!("Unknown JSON SBOM format"),
+            }
+        } else if content.starts_with('<') {
+            let doc = roxmltree::Document::parse(content)?;
+            let namespace = doc
+                .root_element()
+                .tag_name()
+                .namespace()
+                .unwrap_or_default();
+            match namespace.strip_prefix("http://cyclonedx.org/schema/bom/") {
+                Some(version) => Ok(Type::CycloneDX {
+                    format: CycloneDXFormat::Xml,
+                    version: CycloneDXVersion::parse(version)?,
+                }),
+                None => bail!("Unknown XML SBOM format: {namespace}"),
+            }
+        } else if let Some(version) = content.strip_prefix("SPDXVersion:") {
+            check_spdx_version(version.lines().next().unwrap_or_default())?;
+            Ok(Type::Spdx {
+                format: SpdxFormat::TagValue,
+            })
+        } else {
+            bail!("Unknown SBOM format")
+        }
+    }
 }
 
 /// A generator which creates an SBOM for us
@@ -31,7 +134,55 @@ pub trait Generator {
     /// Generate an SBOM from the source
     async fn generate(&self) -> anyhow::Result<Output>;
     /// Find the range to highlight for the provided dependency
-    fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)>;
+    ///
+    /// The `introduced_by` list contains the direct dependencies which pulled in the dependency,
+    /// the nearest ones first. For a direct dependency, or if the graph is unknown, this is the
+    /// dependency itself.
+    fn highlight(
+        &self,
+        dependency: &Dependency,
+        introduced_by: &[Dependency],
+    ) -> anyhow::Result<(Url, Range)>;
+
+    /// The modules of the project, like the ones of a multi-module build.
+    ///
+    /// Modules are matched with the components of the SBOM by their Package URL, ignoring any
+    /// qualifiers.
+    fn modules(&self) -> anyhow::Result<Vec<Module>> {
+        Ok(vec![])
+    }
+
+    /// Find the range to highlight for the provided dependency, in the manifest of a module
+    ///
+    /// The `introduced_by` list contains the direct dependencies of the module.
+    fn highlight_module(
+        &self,
+        _module: &Module,
+        dependency: &Dependency,
+        introduced_by: &[Dependency],
+    ) -> anyhow::Result<(Url, Range)> {
+        self.highlight(dependency, introduced_by)
+    }
+
+    /// The files (and directories) the generated SBOM depends on, like the manifests.
+    ///
+    /// As long as their content doesn't change, a cached SBOM gets used. Without any inputs, the
+    /// SBOM is generated every time.
+    fn inputs(&self) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(vec![])
+    }
+
+    /// Map the scope of a CycloneDX component.
+    ///
+    /// The specification only knows about required, optional and excluded components, so this
+    /// only maps optional ones by default. Generators which know how their tool fills in the
+    /// scope can provide a better mapping.
+    fn scope(&self, scope: &component::Scope) -> Option<Scope> {
+        match scope {
+            component::Scope::Optional => Some(Scope::Optional),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -40,9 +191,36 @@ pub struct Output {
     pub content: Vec<u8>,
 }
 
+/// The generated SBOM of a project, along with the digest of the generator's inputs.
+#[derive(Clone, Debug, Default)]
+pub struct OutputCache {
+    entry: Arc<RwLock<Option<(String, Output)>>>,
+}
+
+impl OutputCache {
+    /// Drop the SBOM, like when the settings of the generator changed
+    pub fn invalidate(&self) {
+        *self.entry.write().unwrap() = None;
+    }
+
+    fn get(&self, digest: &str) -> Option<Output> {
+        match &*self.entry.read().unwrap() {
+            Some((cached, output)) if cached == digest => Some(output.clone()),
+            _ => None,
+        }
+    }
+
+    fn store(&self, digest: String, output: Output) {
+        *self.entry.write().unwrap() = Some((digest, output));
+    }
+}
+
 /// Generate a dependency list from an SBOM.
 pub struct SBOM<G: Generator> {
     generator: G,
+    cache: Option<OutputCache>,
+    graph: RwLock<DependencyGraph>,
+    modules: RwLock<Vec<Module>>,
 }
 
 #[async_trait]
@@ -51,15 +229,65 @@ where
     G: Generator + Send + Sync,
 {
     async fn scan(&self) -> anyhow::Result<Vec<Dependency>> {
-        let Output { r#type, content } = self.generator.generate().await?;
+        let Output { r#type, content } = self.output().await?;
 
-        match r#type {
-            Type::CycloneDX { format, version } => Self::from_cyclonedx(format, version, &content),
-        }
+        let (mut dependencies, mut graph) = match r#type {
+            Type::CycloneDX { format, version } => {
+                self.parse_cyclonedx(format, version, &content)?
+            }
+            Type::Spdx { format } => spdx::parse(format, &content)?,
+        };
+
+        let modules = self.generator.modules()?;
+        let modules = Self::add_modules(modules, &mut dependencies, &mut graph);
+
+        *self.graph.write().unwrap() = graph;
+        *self.modules.write().unwrap() = modules;
+
+        Ok(dependencies)
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        self.graph.read().unwrap().clone()
     }
 
     fn highlight(&self, dependency: &Dependency) -> anyhow::Result<(Url, Range)> {
-        self.generator.highlight(dependency)
+        let mut introduced_by = self
+            .graph
+            .read()
+            .unwrap()
+            .introduced_by(&dependency.purl)
+            .into_iter()
+            .map(Dependency::new)
+            .collect::<Vec<_>>();
+
+        if introduced_by.is_empty() {
+            introduced_by.push(dependency.clone());
+        }
+
+        self.generator.highlight(dependency, &introduced_by)
+    }
+
+    fn modules(&self) -> Vec<Module> {
+        self.modules.read().unwrap().clone()
+    }
+
+    fn highlight_modules(&self, dependency: &Dependency) -> anyhow::Result<Vec<(Url, Range)>> {
+        let result = source::highlight_modules(
+            &self.graph.read().unwrap(),
+            &self.modules.read().unwrap(),
+            dependency,
+            |module, introduced_by| {
+                self.generator
+                    .highlight_module(module, dependency, introduced_by)
+            },
+        )?;
+
+        if result.is_empty() {
+            Ok(vec![self.highlight(dependency)?])
+        } else {
+            Ok(result)
+        }
     }
 }
 
@@ -68,39 +296,360 @@ where
     G: Generator,
 {
     pub fn new(generator: G) -> Self {
-        Self { generator }
+        Self {
+            generator,
+            cache: None,
+            graph: Default::default(),
+            modules: Default::default(),
+        }
+    }
+
+    /// Reuse the generated SBOM, as long as the inputs of the generator don't change
+    pub fn with_cache(mut self, cache: OutputCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Generate the SBOM, unless it is cached
+    async fn output(&self) -> anyhow::Result<Output> {
+        let Some(cache) = &self.cache else {
+            return self.generator.generate().await;
+        };
+
+        // the generator will report a broken manifest better than we could
+        let inputs = self.generator.inputs().unwrap_or_else(|err| {
+            log::info!("Failed to collect the inputs of the generator: {err}");
+            vec![]
+        });
+        if inputs.is_empty() {
+            return self.generator.generate().await;
+        }
+
+        let digest = digest_paths(&inputs)?;
+        if let Some(output) = cache.get(&digest) {
+            log::info!("Using cached SBOM ({digest})");
+            return Ok(output);
+        }
+
+        let output = self.generator.generate().await?;
+        cache.store(digest, output.clone());
+
+        Ok(output)
+    }
+
+    /// Make the modules roots of the graph, using the Package URLs of their components.
+    ///
+    /// Modules are only kept as dependencies if other modules depend on them.
+    fn add_modules(
+        mut modules: Vec<Module>,
+        dependencies: &mut Vec<Dependency>,
+        graph: &mut DependencyGraph,
+    ) -> Vec<Module> {
+        if modules.is_empty() {
+            return modules;
+        }
+
+        let candidates = dependencies
+            .iter()
+            .map(|d| d.purl.clone())
+            .chain(graph.roots().cloned())
+            .collect::<Vec<_>>();
+
+        for module in &mut modules {
+            // the SBOM may qualify the Package URL, like with the type of a Maven artifact
+            if let Some(purl) = candidates
+                .iter()
+                .find(|purl| purl.path() == module.purl.path())
+            {
+                module.purl = purl.clone();
+            }
+            graph.add_root(module.purl.clone());
+        }
+
+        let introductions = graph.introductions();
+        for dependency in dependencies.iter_mut() {
+            introductions.annotate(dependency);
+        }
+        dependencies.retain(|dependency| {
+            !modules.iter().any(|module| module.purl == dependency.purl)
+                || !dependency.introduced_by.is_empty()
+        });
+
+        modules
     }
 
-    fn from_cyclonedx(
+    fn parse_cyclonedx(
+        &self,
         format: CycloneDXFormat,
         version: CycloneDXVersion,
         content: &[u8],
-    ) -> anyhow::Result<Vec<Dependency>> {
+    ) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
         match (format, version) {
             (CycloneDXFormat::Json, CycloneDXVersion::V1_3) => {
-                Self::from_bom(Bom::parse_from_json_v1_3(content)?)
+                self.parse_bom(Bom::parse_from_json_v1_3(content)?)
             }
             (CycloneDXFormat::Xml, CycloneDXVersion::V1_3) => {
-                Self::from_bom(Bom::parse_from_xml_v1_3(content)?)
+                self.parse_bom(Bom::parse_from_xml_v1_3(content)?)
+            }
+            (CycloneDXFormat::Json, CycloneDXVersion::V1_4) => {
+                self.parse_bom(Bom::parse_from_json_v1_4(content)?)
+            }
+            (CycloneDXFormat::Xml, CycloneDXVersion::V1_4) => {
+                self.parse_bom(Bom::parse_from_xml_v1_4(content)?)
+            }
+            (CycloneDXFormat::Json, CycloneDXVersion::V1_5) => {
+                self.parse_bom(Bom::parse_from_json_v1_5(content)?)
+            }
+            (CycloneDXFormat::Xml, CycloneDXVersion::V1_5) => {
+                self.parse_bom(Bom::parse_from_xml_v1_5(content)?)
             }
         }
     }
 
-    /// Convert an SBOM into a vec of dependencies
-    fn from_bom(bom: Bom) -> anyhow::Result<Vec<Dependency>> {
-        bom.components
-            .into_iter()
-            .flat_map(|c| c.0.into_iter())
-            .flat_map(Self::from_component)
-            .collect()
+    /// Convert an SBOM into a vec of dependencies, and their dependency graph
+    fn parse_bom(&self, bom: Bom) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
+        // package URLs, by their BOM reference
+        let mut refs = HashMap::new();
+        let mut roots = HashSet::new();
+
+        if let Some(component) = bom.metadata.and_then(|metadata| metadata.component) {
+            let bom_ref = component.bom_ref.clone();
+            if let Some(root) = self.convert_component(component)? {
+                if let Some(bom_ref) = bom_ref {
+                    refs.insert(bom_ref, root.purl.clone());
+                }
+                roots.insert(root.purl);
+            }
+        }
+
+        let mut dependencies = Vec::new();
+
+        let mut components = Vec::new();
+        Self::flatten(
+            bom.components.map(|c| c.0).unwrap_or_default(),
+            &mut components,
+        );
+
+        for component in components {
+            let bom_ref = component.bom_ref.clone();
+            if let Some(dependency) = self.convert_component(component)? {
+                if let Some(bom_ref) = bom_ref {
+                    refs.insert(bom_ref, dependency.purl.clone());
+                }
+                dependencies.push(dependency);
+            }
+        }
+
+        let bom_has_graph = !roots.is_empty() && bom.dependencies.is_some();
+        let mut graph = DependencyGraph::new(roots);
+
+        for entry in bom.dependencies.into_iter().flat_map(|d| d.0.into_iter()) {
+            if let Some(dependent) = refs.get(&entry.dependency_ref) {
+                for dependency in entry.dependencies.iter().filter_map(|r| refs.get(r)) {
+                    graph.add(dependent.clone(), dependency.clone());
+                }
+            }
+        }
+
+        // without a dependency section, we can't tell
+        if bom_has_graph {
+            let introductions = graph.introductions();
+            for dependency in &mut dependencies {
+                introductions.annotate(dependency);
+            }
+        }
+
+        Ok((dependencies, graph))
+    }
+
+    /// Collect components, including the ones nested in other components
+    fn flatten(components: Vec<Component>, result: &mut Vec<Component>) {
+        for mut component in components {
+            let nested = component.components.take();
+            result.push(component);
+            if let Some(nested) = nested {
+                Self::flatten(nested.0, result);
+            }
+        }
     }
 
     /// Convert a component into a dependency
-    fn from_component(component: Component) -> Option<anyhow::Result<Dependency>> {
-        component.purl.map(|purl| {
-            Ok::<_, anyhow::Error>(Dependency {
-                purl: Url::parse(&purl.to_string())?,
+    fn convert_component(&self, component: Component) -> anyhow::Result<Option<Dependency>> {
+        let Some(purl) = &component.purl else {
+            return Ok(None);
+        };
+
+        let mut dependency = Dependency::new(Url::parse(purl.as_ref())?);
+
+        dependency.scope = component
+            .scope
+            .as_ref()
+            .and_then(|scope| self.generator.scope(scope));
+
+        dependency.checksums = component
+            .hashes
+            .into_iter()
+            .flat_map(|hashes| hashes.0)
+            .map(|hash| Checksum {
+                algorithm: hash.alg.to_string().to_lowercase().replace('-', ""),
+                value: hash.content.0,
+            })
+            .collect();
+
+        dependency.licenses = component
+            .licenses
+            .into_iter()
+            .flat_map(|licenses| licenses.0)
+            .map(|license| match license {
+                LicenseChoice::License(license) => match license.license_identifier {
+                    LicenseIdentifier::SpdxId(id) => id.to_string(),
+                    LicenseIdentifier::Name(name) => name.to_string(),
+                },
+                LicenseChoice::Expression(expression) => expression.to_string(),
             })
-        })
+            .collect();
+
+        Ok(Some(dependency))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::enforcer::source::sbom::{file::FileGenerator, maven::MavenGenerator};
+    use crate::utils::process::Execution;
+
+    fn parse_with<G: Generator>(
+        generator: G,
+        content: &[u8],
+    ) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
+        match Type::detect(content)? {
+            Type::CycloneDX { format, version } => {
+                SBOM::new(generator).parse_cyclonedx(format, version, content)
+            }
+            Type::Spdx { .. } => bail!("Not a CycloneDX SBOM"),
+        }
+    }
+
+    fn parse(content: &[u8]) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
+        parse_with(FileGenerator::new("bom.json"), content)
+    }
+
+    #[test]
+    fn test_parse_json_v1_5() {
+        let (dependencies, graph) = parse(
+            br#"{
+  "bomFormat": "CycloneDX",
+  "specVersion": "1.5",
+  "version": 1,
+  "metadata": {
+    "component": {
+      "type": "application",
+      "bom-ref": "app",
+      "name": "app",
+      "version": "1.0.0",
+      "purl": "pkg:maven/org.example/app@1.0.0"
+    }
+  },
+  "components": [
+    {
+      "type": "library",
+      "bom-ref": "commons-io",
+      "name": "commons-io",
+      "version": "2.11.0",
+      "purl": "pkg:maven/commons-io/commons-io@2.11.0",
+      "hashes": [
+        { "alg": "SHA-256", "content": "961b2f6d87dbacc5d54abf45ab7a6e2495f89b75598962d8c723cea9bc210908" }
+      ],
+      "licenses": [
+        { "license": { "id": "Apache-2.0" } }
+      ]
+    }
+  ],
+  "dependencies": [
+    { "ref": "app", "dependsOn": ["commons-io"] },
+    { "ref": "commons-io", "dependsOn": [] }
+  ]
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(
+            dependencies[0].purl.as_str(),
+            "pkg:maven/commons-io/commons-io@2.11.0"
+        );
+        assert_eq!(dependencies[0].licenses, vec!["Apache-2.0"]);
+        assert_eq!(dependencies[0].checksums[0].algorithm, "sha256");
+        assert_eq!(dependencies[0].direct, Some(true));
+        assert_eq!(graph.roots().count(), 1);
+    }
+
+    #[test]
+    fn test_parse_xml_v1_5() {
+        let (dependencies, _) = parse(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<bom xmlns="http://cyclonedx.org/schema/bom/1.5" version="1">
+  <components>
+    <component type="library" bom-ref="commons-io">
+      <name>commons-io</name>
+      <version>2.11.0</version>
+      <purl>pkg:maven/commons-io/commons-io@2.11.0</purl>
+    </component>
+  </components>
+</bom>"#,
+        )
+        .unwrap();
+
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(
+            dependencies[0].purl.as_str(),
+            "pkg:maven/commons-io/commons-io@2.11.0"
+        );
+    }
+
+    const SCOPES: &[u8] = br#"<?xml version="1.0" encoding="UTF-8"?>
+<bom xmlns="http://cyclonedx.org/schema/bom/1.5" version="1">
+  <components>
+    <component type="library">
+      <name>commons-io</name>
+      <purl>pkg:maven/commons-io/commons-io@2.11.0</purl>
+      <scope>required</scope>
+    </component>
+    <component type="library">
+      <name>servlet-api</name>
+      <purl>pkg:maven/javax.servlet/servlet-api@2.5</purl>
+      <scope>optional</scope>
+    </component>
+    <component type="library">
+      <name>junit</name>
+      <purl>pkg:maven/junit/junit@4.13.2</purl>
+      <scope>excluded</scope>
+    </component>
+  </components>
+</bom>"#;
+
+    #[test]
+    fn test_scopes() {
+        let (dependencies, _) = parse(SCOPES).unwrap();
+
+        let scopes: Vec<_> = dependencies.iter().map(|d| d.scope).collect();
+        assert_eq!(scopes, vec![None, Some(Scope::Optional), None]);
+    }
+
+    #[test]
+    fn test_maven_scopes() {
+        let generator = MavenGenerator::new(".", Execution::default());
+        let (dependencies, _) = parse_with(generator, SCOPES).unwrap();
+
+        let scopes: Vec<_> = dependencies.iter().map(|d| d.scope).collect();
+        assert_eq!(
+            scopes,
+            vec![
+                Some(Scope::Runtime),
+                Some(Scope::Provided),
+                Some(Scope::Test)
+            ]
+        );
     }
 }
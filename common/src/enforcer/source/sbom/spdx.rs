@@ -0,0 +1,332 @@
+//! SPDX 2.x documents, either as JSON or in the tag-value format.
+//!
+//! Packages are identified by the Package URL of their external references. The packages the
+//! document describes are the roots, and the `DEPENDS_ON` (and `*_DEPENDENCY_OF`) relationships
+//! form the graph.
+
+use super::SpdxFormat;
+use crate::enforcer::{source::graph::DependencyGraph, Checksum, Dependency, Scope};
+use anyhow::bail;
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+const DOCUMENT: &str = "SPDXRef-DOCUMENT";
+/// Values of fields which are not known
+const UNKNOWN: &[&str] = &["NOASSERTION", "NONE"];
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    #[serde(default)]
+    document_describes: Vec<String>,
+    #[serde(default)]
+    packages: Vec<Package>,
+    #[serde(default)]
+    relationships: Vec<Relationship>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Package {
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    #[serde(default)]
+    license_concluded: Option<String>,
+    #[serde(default)]
+    license_declared: Option<String>,
+    #[serde(default)]
+    checksums: Vec<PackageChecksum>,
+    #[serde(default)]
+    external_refs: Vec<ExternalRef>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageChecksum {
+    algorithm: String,
+    checksum_value: String,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalRef {
+    reference_type: String,
+    reference_locator: String,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    spdx_element_id: String,
+    relationship_type: String,
+    related_spdx_element: String,
+}
+
+impl Package {
+    fn purl(&self) -> Option<&str> {
+        self.external_refs
+            .iter()
+            .find(|r| r.reference_type == "purl")
+            .map(|r| r.reference_locator.as_str())
+    }
+
+    fn license(&self) -> Option<&str> {
+        [&self.license_declared, &self.license_concluded]
+            .into_iter()
+            .flatten()
+            .map(|license| license.as_str())
+            .find(|license| !UNKNOWN.contains(license))
+    }
+}
+
+/// Parse the tag-value format.
+///
+/// ```text
+/// PackageName: serde
+/// SPDXID: SPDXRef-Package-serde
+/// PackageChecksum: SHA256: 3d8a...
+/// ExternalRef: PACKAGE-MANAGER purl pkg:cargo/serde@1.0.160
+/// Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package-app
+/// ```
+fn parse_tag_value(content: &str) -> anyhow::Result<Document> {
+    let mut document = Document::default();
+    let mut current: Option<Package> = None;
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((tag, value)) = line.split_once(':') else {
+            bail!("Invalid tag-value line: {line}");
+        };
+        let value = value.trim();
+
+        // skip multi-line text
+        if value.starts_with("<text>") && !value.contains("</text>") {
+            for line in lines.by_ref() {
+                if line.contains("</text>") {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        match tag {
+            "PackageName" => {
+                document.packages.extend(current.take());
+                current = Some(Package::default());
+            }
+            // the start of other elements, ending the current package
+            "FileName" | "SnippetSPDXID" | "LicenseID" => {
+                document.packages.extend(current.take());
+            }
+            "SPDXID" => {
+                if let Some(package) = &mut current {
+                    package.spdx_id = value.to_string();
+                }
+            }
+            "PackageLicenseConcluded" => {
+                if let Some(package) = &mut current {
+                    package.license_concluded = Some(value.to_string());
+                }
+            }
+            "PackageLicenseDeclared" => {
+                if let Some(package) = &mut current {
+                    package.license_declared = Some(value.to_string());
+                }
+            }
+            "PackageChecksum" => {
+                if let (Some(package), Some((algorithm, value))) =
+                    (&mut current, value.split_once(':'))
+                {
+                    package.checksums.push(PackageChecksum {
+                        algorithm: algorithm.trim().to_string(),
+                        checksum_value: value.trim().to_string(),
+                    });
+                }
+            }
+            "ExternalRef" => {
+                if let (Some(package), [_, r#type, locator]) = (
+                    &mut current,
+                    &value.split_whitespace().collect::<Vec<_>>()[..],
+                ) {
+                    package.external_refs.push(ExternalRef {
+                        reference_type: r#type.to_string(),
+                        reference_locator: locator.to_string(),
+                    });
+                }
+            }
+            "Relationship" => {
+                if let [element, r#type, related] = value.split_whitespace().collect::<Vec<_>>()[..]
+                {
+                    document.relationships.push(Relationship {
+                        spdx_element_id: element.to_string(),
+                        relationship_type: r#type.to_string(),
+                        related_spdx_element: related.to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    document.packages.extend(current);
+
+    Ok(document)
+}
+
+/// Map a relationship to an edge of the graph, from the dependent to the dependency, and the
+/// scope of the dependency, if the relationship tells.
+fn edge(relationship: &Relationship) -> Option<(&str, &str, Option<Scope>)> {
+    let element = relationship.spdx_element_id.as_str();
+    let related = relationship.related_spdx_element.as_str();
+
+    let scope = match relationship.relationship_type.as_str() {
+        "DEPENDS_ON" => return Some((element, related, None)),
+        "DEPENDENCY_OF" => None,
+        "DEV_DEPENDENCY_OF" => Some(Scope::Dev),
+        "BUILD_DEPENDENCY_OF" => Some(Scope::Build),
+        "TEST_DEPENDENCY_OF" => Some(Scope::Test),
+        "OPTIONAL_DEPENDENCY_OF" => Some(Scope::Optional),
+        "PROVIDED_DEPENDENCY_OF" => Some(Scope::Provided),
+        "RUNTIME_DEPENDENCY_OF" => Some(Scope::Runtime),
+        _ => return None,
+    };
+
+    Some((related, element, scope))
+}
+
+/// Convert an SPDX document into a vec of dependencies, and their dependency graph
+pub fn parse(
+    format: SpdxFormat,
+    content: &[u8],
+) -> anyhow::Result<(Vec<Dependency>, DependencyGraph)> {
+    let document = match format {
+        SpdxFormat::Json => serde_json::from_slice(content)?,
+        SpdxFormat::TagValue => parse_tag_value(std::str::from_utf8(content)?)?,
+    };
+
+    // package URLs, by their SPDX ID
+    let mut refs = HashMap::new();
+    for package in &document.packages {
+        if let Some(purl) = package.purl() {
+            refs.insert(package.spdx_id.as_str(), Url::parse(purl)?);
+        }
+    }
+
+    let described = document
+        .relationships
+        .iter()
+        .filter(|r| r.spdx_element_id == DOCUMENT && r.relationship_type == "DESCRIBES")
+        .map(|r| r.related_spdx_element.as_str())
+        .chain(document.document_describes.iter().map(String::as_str))
+        .collect::<HashSet<_>>();
+
+    let roots = described
+        .iter()
+        .filter_map(|id| refs.get(id).cloned())
+        .collect::<HashSet<_>>();
+
+    let mut graph = DependencyGraph::new(roots.clone());
+    let mut scopes = HashMap::new();
+    let mut has_edges = false;
+
+    for (dependent, dependency, scope) in document.relationships.iter().filter_map(edge) {
+        if let (Some(dependent), Some(dependency)) = (refs.get(dependent), refs.get(dependency)) {
+            graph.add(dependent.clone(), dependency.clone());
+            has_edges = true;
+            if let Some(scope) = scope {
+                scopes.insert(dependency.clone(), scope);
+            }
+        }
+    }
+
+    let introductions = graph.introductions();
+    let mut dependencies = vec![];
+
+    for package in &document.packages {
+        let Some(purl) = refs.get(package.spdx_id.as_str()) else {
+            continue;
+        };
+        // the project itself
+        if roots.contains(purl) {
+            continue;
+        }
+
+        let mut dependency = Dependency::new(purl.clone());
+        dependency.scope = scopes.get(purl).copied();
+        dependency.checksums = package
+            .checksums
+            .iter()
+            .map(|checksum| Checksum {
+                algorithm: checksum.algorithm.to_lowercase().replace('-', ""),
+                value: checksum.checksum_value.to_lowercase(),
+            })
+            .collect();
+        dependency.licenses = package
+            .license()
+            .map(ToString::to_string)
+            .into_iter()
+            .collect();
+
+        // without relationships, we can't tell
+        if !roots.is_empty() && has_edges {
+            introductions.annotate(&mut dependency);
+        }
+
+        dependencies.push(dependency);
+    }
+
+    Ok((dependencies, graph))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag_value() {
+        let (dependencies, graph) = parse(
+            SpdxFormat::TagValue,
+            br#"SPDXVersion: SPDX-2.3
+DataLicense: CC0-1.0
+SPDXID: SPDXRef-DOCUMENT
+DocumentName: app
+
+PackageName: app
+SPDXID: SPDXRef-Package-app
+PackageVersion: 1.0.0
+ExternalRef: PACKAGE-MANAGER purl pkg:cargo/app@1.0.0
+
+PackageName: serde
+SPDXID: SPDXRef-Package-serde
+PackageVersion: 1.0.160
+PackageChecksum: SHA256: 3D8A8D2B4A2E1C0C5B1F5E2E3E8F4E2B0A1A6C6B2F8A9B3C5D7E9F1A3B5C7D9E
+PackageLicenseConcluded: NOASSERTION
+PackageLicenseDeclared: MIT OR Apache-2.0
+PackageDescription: <text>A generic serialization
+framework</text>
+ExternalRef: PACKAGE-MANAGER purl pkg:cargo/serde@1.0.160
+
+PackageName: serde_test
+SPDXID: SPDXRef-Package-serde-test
+ExternalRef: PACKAGE-MANAGER purl pkg:cargo/serde_test@1.0.160
+
+Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package-app
+Relationship: SPDXRef-Package-app DEPENDS_ON SPDXRef-Package-serde
+Relationship: SPDXRef-Package-serde-test DEV_DEPENDENCY_OF SPDXRef-Package-app
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(dependencies.len(), 2);
+        assert_eq!(dependencies[0].purl.as_str(), "pkg:cargo/serde@1.0.160");
+        assert_eq!(dependencies[0].licenses, vec!["MIT OR Apache-2.0"]);
+        assert_eq!(dependencies[0].checksums[0].algorithm, "sha256");
+        assert_eq!(dependencies[0].direct, Some(true));
+        assert_eq!(dependencies[1].scope, Some(Scope::Dev));
+        assert_eq!(graph.roots().count(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use ropey::Rope;
 use roxmltree::{Document, Node};
+use serde::de::DeserializeOwned;
 use std::{
     fmt::{Display, Formatter},
     ops::{Deref, DerefMut},
@@ -84,20 +85,7 @@ impl<'a> Highlighter<'a> {
     }
 
     pub fn full_range(&self) -> Range {
-        let lines = self.rope.len_lines();
-
-        let lines = if lines == 0 {
-            return Default::default();
-        } else {
-            lines - 1
-        };
-
-        let end = Position {
-            line: lines,
-            position: self.rope.line(lines).len_bytes(),
-        };
-
-        Range(Position::default()..end)
+        full_range(&self.rope)
     }
 
     pub fn find_with<'r, F>(&'a self, f: F) -> anyhow::Result<Option<Range>>
@@ -106,7 +94,7 @@ impl<'a> Highlighter<'a> {
         'a: 'r,
     {
         Ok(match f(&self.doc) {
-            Some(node) => Some(Range(self.make_range(node.range())?)),
+            Some(node) => Some(Range(make_range(&self.rope, node.range())?)),
             None => None,
         })
     }
@@ -117,22 +105,107 @@ impl<'a> Highlighter<'a> {
     {
         self.find_with(|doc| doc.descendants().find(predicate))
     }
+}
+
+/// A highlighter for TOML documents.
+///
+/// The document gets deserialized into `T`, which should use [`toml::Spanned`] for the elements
+/// it wants to locate.
+pub struct TomlHighlighter<T> {
+    rope: Rope,
+    doc: T,
+}
+
+impl<T> TomlHighlighter<T>
+where
+    T: DeserializeOwned,
+{
+    pub fn new(content: &str) -> anyhow::Result<Self> {
+        let rope = Rope::from_str(content);
+        let doc = toml::from_str(content)?;
+
+        Ok(Self { rope, doc })
+    }
+
+    pub fn full_range(&self) -> Range {
+        full_range(&self.rope)
+    }
 
-    fn make_range(
-        &self,
-        range: std::ops::Range<usize>,
-    ) -> anyhow::Result<std::ops::Range<Position>> {
-        Ok(std::ops::Range {
-            start: self.make_position(range.start)?,
-            end: self.make_position(range.end)?,
+    /// Find a range, using the byte span returned by the function.
+    pub fn find_with<F>(&self, f: F) -> anyhow::Result<Option<Range>>
+    where
+        F: FnOnce(&T) -> Option<std::ops::Range<usize>>,
+    {
+        Ok(match f(&self.doc) {
+            Some(span) => Some(Range(make_range(&self.rope, span)?)),
+            None => None,
         })
     }
+}
 
-    fn make_position(&self, position: usize) -> anyhow::Result<Position> {
-        let line = self.rope.try_byte_to_line(position)?;
-        let position = position - self.rope.try_line_to_byte(line)?;
-        Ok(Position { line, position })
+/// A highlighter for documents without a parser of their own.
+///
+/// The caller locates the byte span in the text, e.g. a line of a `requirements.txt` file.
+pub struct TextHighlighter<'a> {
+    rope: Rope,
+    content: &'a str,
+}
+
+impl<'a> TextHighlighter<'a> {
+    pub fn new(content: &'a str) -> Self {
+        Self {
+            rope: Rope::from_str(content),
+            content,
+        }
+    }
+
+    pub fn full_range(&self) -> Range {
+        full_range(&self.rope)
     }
+
+    /// Find a range, using the byte span returned by the function.
+    pub fn find_with<F>(&self, f: F) -> anyhow::Result<Option<Range>>
+    where
+        F: FnOnce(&'a str) -> Option<std::ops::Range<usize>>,
+    {
+        Ok(match f(self.content) {
+            Some(span) => Some(Range(make_range(&self.rope, span)?)),
+            None => None,
+        })
+    }
+}
+
+fn full_range(rope: &Rope) -> Range {
+    let lines = rope.len_lines();
+
+    let lines = if lines == 0 {
+        return Default::default();
+    } else {
+        lines - 1
+    };
+
+    let end = Position {
+        line: lines,
+        position: rope.line(lines).len_bytes(),
+    };
+
+    Range(Position::default()..end)
+}
+
+fn make_range(
+    rope: &Rope,
+    range: std::ops::Range<usize>,
+) -> anyhow::Result<std::ops::Range<Position>> {
+    Ok(std::ops::Range {
+        start: make_position(rope, range.start)?,
+        end: make_position(rope, range.end)?,
+    })
+}
+
+fn make_position(rope: &Rope, position: usize) -> anyhow::Result<Position> {
+    let line = rope.try_byte_to_line(position)?;
+    let position = position - rope.try_line_to_byte(line)?;
+    Ok(Position { line, position })
 }
 
 #[cfg(test)]
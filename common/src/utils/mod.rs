@@ -2,9 +2,11 @@ use lsp_types::{Position, Range};
 use ropey::Rope;
 
 pub mod pool;
+pub mod process;
 pub mod progress;
 pub mod projects;
 pub mod rationale;
+pub mod wildcard;
 
 pub fn span_to_range(content: &Rope, span: std::ops::Range<usize>) -> Option<Range> {
     fn convert(content: &Rope, span: std::ops::Range<usize>) -> Result<Range, ropey::Error> {
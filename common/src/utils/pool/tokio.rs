@@ -5,23 +5,49 @@ use tokio_util::task::LocalPoolHandle;
 #[derive(Clone, Debug)]
 pub struct Pool {
     pool: LocalPoolHandle,
+    size: usize,
 }
 
+const DEFAULT_SIZE: usize = 8;
+
 impl Pool {
     pub fn new() -> Self {
         Self {
-            pool: LocalPoolHandle::new(8),
+            pool: LocalPoolHandle::new(DEFAULT_SIZE),
+            size: DEFAULT_SIZE,
         }
     }
 
-    pub async fn spawn_pinned<F, Fut>(&self, f: F) -> Result<Fut::Output, PoolError>
+    /// The number of threads of the pool
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Spawn a task, pinned to a single worker.
+    ///
+    /// The task is spawned right away, not only when the returned future gets polled.
+    pub fn spawn_pinned<F, Fut>(
+        &self,
+        f: F,
+    ) -> impl Future<Output = Result<Fut::Output, PoolError>> + Send
     where
         F: FnOnce() -> Fut,
         F: Send + 'static,
         Fut: Future + 'static,
         Fut::Output: Send + 'static,
     {
-        self.pool.spawn_pinned(f).await.map_err(|_err| PoolError)
+        let handle = self.pool.spawn_pinned(f);
+        async move { handle.await.map_err(|_err| PoolError) }
+    }
+
+    /// Spawn a long running task, pinned to a single worker, without waiting for it.
+    pub fn spawn_detached<F, Fut>(&self, f: F)
+    where
+        F: FnOnce() -> Fut,
+        F: Send + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let _ = self.pool.spawn_pinned(f);
     }
 }
 
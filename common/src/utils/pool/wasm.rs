@@ -10,7 +10,15 @@ impl Pool {
         Self {}
     }
 
-    pub async fn spawn_pinned<F, Fut>(&self, f: F) -> Result<Fut::Output, PoolError>
+    /// The number of threads of the pool
+    pub fn size(&self) -> usize {
+        1
+    }
+
+    /// Spawn a task, pinned to a single worker.
+    ///
+    /// The task is spawned right away, not only when the returned future gets polled.
+    pub fn spawn_pinned<F, Fut>(&self, f: F) -> impl Future<Output = Result<Fut::Output, PoolError>>
     where
         F: FnOnce() -> Fut + 'static,
         F: Send + 'static,
@@ -22,6 +30,18 @@ impl Pool {
             let _ = tx.send(f().await);
         });
 
-        rx.await.map_err(|_err| PoolError)
+        async move { rx.await.map_err(|_err| PoolError) }
+    }
+
+    /// Spawn a long running task, pinned to a single worker, without waiting for it.
+    pub fn spawn_detached<F, Fut>(&self, f: F)
+    where
+        F: FnOnce() -> Fut,
+        F: Send + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(async move {
+            f().await;
+        });
     }
 }
@@ -0,0 +1,119 @@
+//! Running external processes, like the build tools generating an SBOM.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod tokio;
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::tokio::*;
+
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+#[cfg(target_arch = "wasm32")]
+pub use self::wasm::*;
+
+use ::tokio::sync::mpsc::UnboundedSender;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// The time after which a process gets killed, unless configured otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The number of lines of output kept for reporting a failure
+const TAIL_LINES: usize = 20;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to run '{command}': {source}")]
+    Io {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error("'{command}' failed ({status}):\n{log}")]
+    Failed {
+        command: String,
+        status: String,
+        log: String,
+    },
+    #[error("'{command}' timed out after {} seconds:\n{log}", timeout.as_secs())]
+    Timeout {
+        command: String,
+        timeout: Duration,
+        log: String,
+    },
+    #[error("'{command}' was cancelled")]
+    Cancelled { command: String },
+    #[error("running '{command}' is not supported on this platform")]
+    Unsupported { command: String },
+    #[error("'{command}' was not run, as the project is not trusted. Trust the project to gather its dependencies.")]
+    Untrusted { command: String },
+}
+
+/// Whether the build tools of a project may run, which executes code of the project.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Trust {
+    Trusted,
+    /// Only read the files of a project, like lockfiles
+    #[default]
+    Untrusted,
+}
+
+/// How to run a process
+#[derive(Clone, Debug)]
+pub struct Execution {
+    /// The time after which the process gets killed
+    pub timeout: Duration,
+    /// Kills the process once cancelled
+    pub cancel: CancellationToken,
+    /// Receives each line the process writes, to stdout or stderr
+    pub output: Option<UnboundedSender<String>>,
+    /// Processes only run for trusted projects
+    pub trust: Trust,
+}
+
+impl Default for Execution {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            cancel: CancellationToken::new(),
+            output: None,
+            trust: Trust::default(),
+        }
+    }
+}
+
+impl Execution {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn with_output(mut self, output: UnboundedSender<String>) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    pub fn with_trust(mut self, trust: Trust) -> Self {
+        self.trust = trust;
+        self
+    }
+
+    /// Check if a command may run, which requires a trusted project.
+    pub fn check(&self, command: impl Into<String>) -> Result<(), Error> {
+        match self.trust {
+            Trust::Trusted => Ok(()),
+            Trust::Untrusted => Err(Error::Untrusted {
+                command: command.into(),
+            }),
+        }
+    }
+}
+
+/// The name of the program of a command, for reporting
+fn program(command: &std::process::Command) -> String {
+    command.get_program().to_string_lossy().to_string()
+}
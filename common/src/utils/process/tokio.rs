@@ -0,0 +1,135 @@
+use super::{program, Error, Execution, TAIL_LINES};
+use std::{collections::VecDeque, process::Stdio, sync::Mutex};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader},
+    process::Command,
+    sync::mpsc::UnboundedSender,
+};
+
+/// Run a process to completion.
+///
+/// The output of the process is forwarded line by line, and the last lines of it are part of the
+/// error, if the process fails or times out.
+pub async fn run(command: std::process::Command, execution: &Execution) -> Result<(), Error> {
+    execute(command, execution, false).await.map(|_| ())
+}
+
+/// Run a process to completion, returning what it writes to stdout.
+///
+/// Like [`run`], but only the lines written to stderr are forwarded.
+pub async fn output(
+    command: std::process::Command,
+    execution: &Execution,
+) -> Result<Vec<u8>, Error> {
+    execute(command, execution, true).await
+}
+
+async fn execute(
+    command: std::process::Command,
+    execution: &Execution,
+    capture: bool,
+) -> Result<Vec<u8>, Error> {
+    let name = program(&command);
+    execution.check(&name)?;
+
+    let mut command = Command::from(command);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|source| Error::Io {
+            command: name.clone(),
+            source,
+        })?;
+
+    let tail = Mutex::new(VecDeque::with_capacity(TAIL_LINES));
+    let output = execution.output.as_ref();
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    let mut captured = vec![];
+
+    let result = {
+        let run = async {
+            if capture {
+                tokio::join!(
+                    read_all(stdout, &mut captured),
+                    forward(stderr, &tail, output)
+                );
+            } else {
+                tokio::join!(
+                    forward(stdout, &tail, output),
+                    forward(stderr, &tail, output)
+                );
+            }
+            child.wait().await
+        };
+
+        tokio::select! {
+            result = tokio::time::timeout(execution.timeout, run) => Some(result),
+            _ = execution.cancel.cancelled() => None,
+        }
+    };
+
+    let lines = || Vec::from(tail.lock().unwrap().clone()).join("\n");
+
+    match result {
+        Some(Ok(Ok(status))) if status.success() => Ok(captured),
+        Some(Ok(Ok(status))) => Err(Error::Failed {
+            command: name,
+            status: status.to_string(),
+            log: lines(),
+        }),
+        Some(Ok(Err(source))) => Err(Error::Io {
+            command: name,
+            source,
+        }),
+        Some(Err(_)) => {
+            let _ = child.kill().await;
+            Err(Error::Timeout {
+                command: name,
+                timeout: execution.timeout,
+                log: lines(),
+            })
+        }
+        None => {
+            log::info!("Cancelled: {name}");
+            let _ = child.kill().await;
+            Err(Error::Cancelled { command: name })
+        }
+    }
+}
+
+/// Read all of a stream
+async fn read_all(stream: Option<impl AsyncRead + Unpin>, buffer: &mut Vec<u8>) {
+    if let Some(mut stream) = stream {
+        if let Err(err) = stream.read_to_end(buffer).await {
+            log::warn!("Failed to read the output: {err}");
+        }
+    }
+}
+
+/// Forward the lines of a stream, keeping the last ones
+async fn forward(
+    stream: Option<impl AsyncRead + Unpin>,
+    tail: &Mutex<VecDeque<String>>,
+    output: Option<&UnboundedSender<String>>,
+) {
+    let Some(stream) = stream else {
+        return;
+    };
+
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        log::debug!("{line}");
+        if let Some(output) = output {
+            let _ = output.send(line.clone());
+        }
+
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+}
@@ -0,0 +1,24 @@
+use super::{program, Error, Execution};
+
+/// Run a process to completion.
+///
+/// Processes can't be spawned from WebAssembly, so this always fails.
+pub async fn run(command: std::process::Command, execution: &Execution) -> Result<(), Error> {
+    let name = program(&command);
+    execution.check(&name)?;
+
+    Err(Error::Unsupported { command: name })
+}
+
+/// Run a process to completion, returning what it writes to stdout.
+///
+/// Processes can't be spawned from WebAssembly, so this always fails.
+pub async fn output(
+    command: std::process::Command,
+    execution: &Execution,
+) -> Result<Vec<u8>, Error> {
+    let name = program(&command);
+    execution.check(&name)?;
+
+    Err(Error::Unsupported { command: name })
+}
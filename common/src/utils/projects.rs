@@ -1,2 +1,53 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
 pub const CARGO_FILE: &str = "Cargo.toml";
+pub const CARGO_LOCK_FILE: &str = "Cargo.lock";
 pub const MAVEN_FILE: &str = "pom.xml";
+pub const MAVEN_CONFIG_DIR: &str = ".mvn";
+pub const NPM_FILE: &str = "package.json";
+pub const NPM_LOCK_FILE: &str = "package-lock.json";
+pub const YARN_LOCK_FILE: &str = "yarn.lock";
+pub const PNPM_LOCK_FILE: &str = "pnpm-lock.yaml";
+pub const PYPROJECT_FILE: &str = "pyproject.toml";
+pub const POETRY_LOCK_FILE: &str = "poetry.lock";
+pub const UV_LOCK_FILE: &str = "uv.lock";
+pub const PIPFILE: &str = "Pipfile";
+pub const PIPFILE_LOCK: &str = "Pipfile.lock";
+pub const REQUIREMENTS_FILE: &str = "requirements.txt";
+pub const GO_MOD_FILE: &str = "go.mod";
+pub const GO_SUM_FILE: &str = "go.sum";
+pub const GRADLE_FILE: &str = "build.gradle";
+pub const GRADLE_KTS_FILE: &str = "build.gradle.kts";
+pub const GRADLE_SETTINGS_FILE: &str = "settings.gradle";
+pub const GRADLE_SETTINGS_KTS_FILE: &str = "settings.gradle.kts";
+pub const GRADLE_PROPERTIES_FILE: &str = "gradle.properties";
+pub const GRADLE_LOCK_FILE: &str = "gradle.lockfile";
+pub const GRADLE_BUILDSCRIPT_LOCK_FILE: &str = "buildscript-gradle.lockfile";
+pub const GRADLE_DIR: &str = "gradle";
+pub const GRADLE_VERSION_CATALOG: &str = "gradle/libs.versions.toml";
+
+/// Check if a file name is the one of a pip requirements file, like `requirements-dev.txt`.
+pub fn is_requirements_file(name: &str) -> bool {
+    name.starts_with("requirements") && name.ends_with(".txt")
+}
+
+/// Find the `requirements*.txt` files of a project, sorted by name.
+pub fn requirements_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        let is_requirements = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(is_requirements_file)
+            .unwrap_or_default();
+        if is_requirements && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
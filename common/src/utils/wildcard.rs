@@ -0,0 +1,46 @@
+//! Simple wildcard matching
+
+/// Match a value against a pattern, using `*` as a wildcard for any number of characters.
+pub fn matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    let Some(mut remaining) = parts.next().and_then(|first| value.strip_prefix(first)) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // no wildcard at all
+        return remaining.is_empty();
+    };
+
+    for part in middle {
+        match remaining.find(part) {
+            Some(idx) => remaining = &remaining[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    remaining.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("pkg:cargo/serde@1.0.0", "pkg:cargo/serde@1.0.0"));
+        assert!(!matches("pkg:cargo/serde@1.0.0", "pkg:cargo/serde@1.0.1"));
+        assert!(matches(
+            "pkg:maven/*",
+            "pkg:maven/io.quarkus/quarkus-core@2.9.2"
+        ));
+        assert!(!matches("pkg:maven/*", "pkg:cargo/serde@1.0.0"));
+        assert!(matches("pkg:*/serde@*", "pkg:cargo/serde@1.0.0"));
+        assert!(matches("*@1.0.0", "pkg:cargo/serde@1.0.0"));
+        assert!(!matches("a*a", "a"));
+        assert!(!matches("*ab*ba", "aba"));
+        assert!(matches("*", ""));
+    }
+}
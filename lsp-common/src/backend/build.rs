@@ -0,0 +1,59 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tokio_util::sync::CancellationToken;
+
+/// The builds which are currently running.
+///
+/// Builds hold the lock of the workspace while running, so they are tracked outside of it, which
+/// allows cancelling a build once one of its files changes again.
+#[derive(Clone, Debug, Default)]
+pub struct Builds {
+    running: Arc<Mutex<HashMap<PathBuf, Build>>>,
+}
+
+#[derive(Debug)]
+struct Build {
+    /// The files tracked by the build
+    tracked: Vec<PathBuf>,
+    cancel: CancellationToken,
+}
+
+impl Builds {
+    /// Start the build of a file, cancelling a previous one still running.
+    pub fn start(&self, path: &Path, tracked: &[PathBuf]) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        let build = Build {
+            tracked: tracked.to_vec(),
+            cancel: cancel.clone(),
+        };
+
+        if let Some(previous) = self
+            .running
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), build)
+        {
+            previous.cancel.cancel();
+        }
+
+        cancel
+    }
+
+    /// Mark the build of a file as finished.
+    pub fn finish(&self, path: &Path) {
+        self.running.lock().unwrap().remove(path);
+    }
+
+    /// Cancel all builds tracking a changed file, as a newer build will follow.
+    pub fn cancel(&self, changed: &Path) {
+        for (path, build) in self.running.lock().unwrap().iter() {
+            if build.tracked.iter().any(|f| f == changed) {
+                log::info!("Cancelling build of: {}", path.display());
+                build.cancel.cancel();
+            }
+        }
+    }
+}
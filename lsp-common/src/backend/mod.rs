@@ -3,6 +3,7 @@
 //! Project: An actual project that we want to work with. Found in the root- or sub-folder.
 
 use crate::backend::workspace::Workspace;
+use seedwing_enforcer_common::utils::process::Trust;
 use std::{cell::Cell, sync::Mutex};
 use tower_lsp::{
     jsonrpc::{Error, ErrorCode, Result},
@@ -10,12 +11,22 @@ use tower_lsp::{
     Client, LanguageServer,
 };
 
+mod build;
 mod notification;
 mod project;
 mod workspace;
 
 pub mod progress;
 
+/// Options sent by the client, when initializing
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InitializationOptions {
+    /// Trust the workspace, allowing to run the build tools of its projects
+    #[serde(default)]
+    trusted: bool,
+}
+
 pub struct Backend {
     pub client: Client,
     workspace: Workspace,
@@ -27,6 +38,21 @@ impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
         log::info!("Workspaces: {:?}", params.workspace_folders);
 
+        let options = match params.initialization_options {
+            Some(options) => serde_json::from_value(options).unwrap_or_else(|err| {
+                log::warn!("Invalid initialization options: {err}");
+                InitializationOptions::default()
+            }),
+            None => InitializationOptions::default(),
+        };
+        let trust = if options.trusted {
+            Trust::Trusted
+        } else {
+            Trust::Untrusted
+        };
+        log::info!("Workspace trust: {trust:?}");
+        self.workspace.set_trust(trust).await;
+
         // remember how we got initialized, unfortunately we cannot set it up yet, as we can't
         // send notification before the `initialized` function got called.
 
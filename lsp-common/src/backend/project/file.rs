@@ -1,5 +1,6 @@
 use crate::{
     backend::{
+        build::Builds,
         notification::{UpdatedDependencies, UpdatedDependenciesParameters},
         progress::{run_operation, ClientProgress},
         project::publisher::{Category, DiagnosticPublisher},
@@ -7,22 +8,27 @@ use crate::{
     protocol::{commands::SHOW_REPORT, types::Report},
 };
 use seedwing_enforcer_common::{
+    config::{GeneratorConfig, ManifestType, MavenResolver, PROJECT_RULE},
     enforcer::{
         seedwing::{self, render::ResponseRenderer, Evaluator},
-        source::{
-            sbom::{maven::MavenGenerator, SBOM},
-            Source,
-        },
-        Dependency,
+        source::{sbom::OutputCache, AutoSource},
+        Dependency, Project,
     },
     highlight,
+    utils::process::{self, Execution, Trust},
 };
 use seedwing_policy_engine::{
     lang::Severity,
     runtime::{response::Collector, Response},
 };
 use serde_json::Value;
-use std::{collections::HashMap, io, path::PathBuf};
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tower_lsp::{
     lsp_types::{
         CodeActionContext, CodeActionOrCommand, CodeLens, Command, Diagnostic, DiagnosticSeverity,
@@ -42,28 +48,78 @@ pub enum Error {
     Io(#[from] io::Error),
 }
 
+impl Error {
+    /// The error of a source refusing to run a build tool, as the project isn't trusted
+    fn untrusted(&self) -> Option<&process::Error> {
+        match self {
+            Self::Source(err) => err
+                .downcast_ref::<process::Error>()
+                .filter(|err| matches!(err, process::Error::Untrusted { .. })),
+            _ => None,
+        }
+    }
+}
+
 /// A file in a project which is subject of being verified.
 #[derive(Debug)]
 pub struct File {
+    root: PathBuf,
+    r#type: ManifestType,
     path: PathBuf,
+    /// Files which have an impact on the outcome, including the file itself.
+    tracked: Vec<PathBuf>,
     enforcer: Evaluator,
     client: Client,
+    builds: Builds,
+    trust: Trust,
+    /// The SBOM generated by a build tool, reused until the manifests or the settings change
+    outputs: OutputCache,
+    /// The settings the SBOM was generated with
+    settings: Option<(GeneratorConfig, MavenResolver, Trust)>,
 
     dependencies: Vec<Dependency>,
     diagnostics: HashMap<Url, Vec<Diagnostic>>,
 }
 
 impl File {
-    pub fn new(path: PathBuf, client: Client, enforcer: Evaluator) -> Self {
+    pub fn new(
+        root: &Path,
+        r#type: ManifestType,
+        client: Client,
+        enforcer: Evaluator,
+        builds: Builds,
+        trust: Trust,
+    ) -> Self {
         Self {
-            path,
+            root: root.to_path_buf(),
+            r#type,
+            path: r#type.manifest_path(root),
+            tracked: r#type.paths(root),
             enforcer,
             client,
+            builds,
+            trust,
+            outputs: Default::default(),
+            settings: None,
             dependencies: Default::default(),
             diagnostics: Default::default(),
         }
     }
 
+    /// Also track a file outside of the project's own files, like a configured SBOM
+    pub fn track(&mut self, path: PathBuf) {
+        if !self.tracked.contains(&path) {
+            self.tracked.push(path);
+        }
+    }
+
+    /// Check if a changed file requires this file to be re-built
+    ///
+    /// This includes files of the project which didn't exist when the file was created.
+    pub fn tracks(&self, path: &Path) -> bool {
+        self.tracked.iter().any(|f| f == path) || self.r#type.is_file(&self.root, path)
+    }
+
     /// build the project, which in this case means to gather and validate dependencies
     pub async fn build(&mut self, publisher: &mut DiagnosticPublisher) {
         let root = match Url::from_file_path(&self.path) {
@@ -71,19 +127,40 @@ impl File {
             Err(_) => return,
         };
 
-        match self.process().await {
+        // files of the project might have shown up since
+        for path in self.r#type.paths(&self.root) {
+            self.track(path);
+        }
+
+        let cancel = self.builds.start(&self.path, &self.tracked);
+        let result = self.process(cancel.clone()).await;
+        self.builds.finish(&self.path);
+
+        if cancel.is_cancelled() {
+            // a newer build will publish its outcome
+            log::info!("Build cancelled: {}", self.path.display());
+            return;
+        }
+
+        match result {
             Ok(()) => {}
             Err(err) => {
                 log::warn!("Failed to run: {err}");
                 self.dependencies.clear();
-                self.diagnostics = HashMap::from([(
-                    root.clone(),
-                    vec![Diagnostic {
+                // skipping the build tools of an untrusted project isn't a failure
+                let diagnostic = match err.untrusted() {
+                    Some(err) => Diagnostic {
+                        message: err.to_string(),
+                        severity: Some(DiagnosticSeverity::WARNING),
+                        ..Default::default()
+                    },
+                    None => Diagnostic {
                         message: format!("Failed to run enforcer: {err}"),
                         severity: Some(DiagnosticSeverity::ERROR),
                         ..Default::default()
-                    }],
-                )]);
+                    },
+                };
+                self.diagnostics = HashMap::from([(root.clone(), vec![diagnostic])]);
             }
         }
 
@@ -104,22 +181,64 @@ impl File {
             .await;
     }
 
-    async fn process(&mut self) -> Result<(), Error> {
+    async fn process(&mut self, cancel: CancellationToken) -> Result<(), Error> {
         let root = match self.path.parent() {
             Some(parent) => parent,
             None => return Ok(()),
         };
 
         // refresh dependencies
-        let source = SBOM::new(MavenGenerator::new(root));
+        let config = self.enforcer.get_config().await;
+
+        // an SBOM generated differently is outdated, even if the manifests are the same
+        let (generator, resolver) = config
+            .as_ref()
+            .map(|config| {
+                (
+                    config.enforcer.generator.clone(),
+                    config.enforcer.maven.resolver,
+                )
+            })
+            .unwrap_or_default();
+        let settings = Some((generator, resolver, self.trust));
+        if self.settings != settings {
+            self.outputs.invalidate();
+            self.settings = settings;
+        }
+
+        let (output, mut lines) = mpsc::unbounded_channel();
+        let execution = Execution::default()
+            .with_trust(self.trust)
+            .with_cancel(cancel)
+            .with_output(output);
+        let source =
+            AutoSource::find_source(root, config.clone(), execution, Some(self.outputs.clone()))
+                .await
+                .map_err(Error::Source)?;
+        let scan = source.scan();
         self.dependencies = run_operation(
             self.client.clone(),
             "Gathering dependencies",
             1,
-            |_progress| async { source.scan().await.map_err(Error::Source) },
+            |progress| async move {
+                tokio::pin!(scan);
+
+                // report the output of build tools, while they run
+                loop {
+                    tokio::select! {
+                        result = &mut scan => break result.map_err(Error::Source),
+                        Some(line) = lines.recv() => progress.update(line, None::<usize>).await,
+                    }
+                }
+            },
         )
         .await?;
 
+        // changes to the manifest of any module require a re-build
+        for module in source.modules() {
+            self.track(module.manifest);
+        }
+
         // evaluate policies
 
         let response = self
@@ -132,6 +251,7 @@ impl File {
 
         // render diagnostics
 
+        let manifest = Url::from_file_path(&self.path).ok();
         let mut diags = HashMap::<Url, Vec<Diagnostic>>::new();
 
         for (dependency, response) in response {
@@ -140,45 +260,119 @@ impl File {
                     // ignore succeeded entries
                 }
                 severity => {
-                    if let Ok((url, range)) = source.highlight(&dependency) {
-                        diags.entry(url).or_default().push({
-                            let collected = Collector::new(&response).highest_severity().collect();
-                            let message = collected
-                                .iter()
-                                .map(|r| r.reason.as_str())
-                                .collect::<Vec<_>>()
-                                .join(", ");
-
-                            Diagnostic {
-                                severity: match severity {
-                                    Severity::None => None,
-                                    Severity::Advice => Some(DiagnosticSeverity::INFORMATION),
-                                    Severity::Warning => Some(DiagnosticSeverity::WARNING),
-                                    Severity::Error => Some(DiagnosticSeverity::ERROR),
-                                },
-                                message: format!("{}: {}", dependency.purl, message),
-                                range: range.into(),
-                                data: Self::make_data(&dependency, &collected, &response).ok(),
-                                ..Default::default()
+                    // one location for each module pulling in the dependency
+                    let mut locations =
+                        source.highlight_modules(&dependency).unwrap_or_else(|err| {
+                            log::info!("Failed to highlight {dependency} in the modules: {err}");
+                            vec![]
+                        });
+                    // a dependency no module can be found for, like one inherited from a parent
+                    // outside of the build, is reported on the manifest
+                    if locations.is_empty() {
+                        match source.highlight(&dependency) {
+                            Ok(location) => locations.push(location),
+                            Err(err) => {
+                                log::info!("Failed to highlight {dependency}: {err}");
+                                locations
+                                    .extend(manifest.clone().map(|url| (url, Default::default())));
                             }
+                        }
+                    }
+
+                    let diagnostic = {
+                        let collected = Collector::new(&response).highest_severity().collect();
+                        let message = response
+                            .rationale
+                            .iter()
+                            .filter(|rule| rule.severity != Severity::None)
+                            .map(|rule| {
+                                let reasons = Collector::new(rule)
+                                    .highest_severity()
+                                    .collect()
+                                    .iter()
+                                    .map(|r| r.reason.clone())
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                let name = rule.name.to_string();
+                                match config
+                                    .as_ref()
+                                    .and_then(|config| seedwing::waiver(config, &dependency, rule))
+                                {
+                                    Some(exception) => {
+                                        format!("{name}: {reasons} (waived: {})", exception.reason)
+                                    }
+                                    None => format!("{name}: {reasons}"),
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join("; ");
+
+                        Diagnostic {
+                            severity: to_diagnostic_severity(severity),
+                            message: match dependency.path() {
+                                Some(path) => format!("{path}: {message}"),
+                                None => format!("{}: {}", dependency.purl, message),
+                            },
+                            data: Self::make_data(
+                                dependency.purl.to_string(),
+                                &collected,
+                                &response,
+                            )
+                            .ok(),
+                            ..Default::default()
+                        }
+                    };
+
+                    for (url, range) in locations {
+                        diags.entry(url).or_default().push(Diagnostic {
+                            range: range.into(),
+                            ..diagnostic.clone()
                         });
                     }
                 }
             }
         }
 
+        // evaluate the project as a whole, reporting on the manifest
+
+        let project = Project {
+            roots: source.graph().roots().cloned().collect(),
+            dependencies: self.dependencies.clone(),
+        };
+
+        if let Some(response) = self.enforcer.eval_project(project).await? {
+            if let (Some(severity), Ok(url)) = (
+                to_diagnostic_severity(response.severity),
+                Url::from_file_path(&self.path),
+            ) {
+                let collected = Collector::new(&response).highest_severity().collect();
+                let reasons = collected
+                    .iter()
+                    .map(|r| r.reason.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                diags.entry(url).or_default().push(Diagnostic {
+                    severity: Some(severity),
+                    message: format!("{PROJECT_RULE}: {reasons}"),
+                    data: Self::make_data(PROJECT_RULE.to_string(), &collected, &response).ok(),
+                    ..Default::default()
+                });
+            }
+        }
+
         self.diagnostics = diags;
 
         Ok(())
     }
 
     fn make_data(
-        dependency: &Dependency,
+        title: String,
         collected: &[Response],
         original: &Response,
     ) -> anyhow::Result<Value> {
         Ok(serde_json::to_value(&Report {
-            title: dependency.purl.to_string(),
+            title,
             html: format!(
                 r#"
 <div class="swe-response">
@@ -199,13 +393,14 @@ impl File {
         })?)
     }
 
-    pub async fn code_lens(&self) -> anyhow::Result<Vec<CodeLens>> {
-        let root = match Url::from_file_path(&self.path) {
+    /// Code lenses for a file tracked by this one, like the manifest of a module
+    pub async fn code_lens(&self, path: &Path) -> anyhow::Result<Vec<CodeLens>> {
+        let url = match Url::from_file_path(path) {
             Ok(url) => url,
             Err(_) => return Ok(vec![]),
         };
 
-        if let Some(diags) = self.diagnostics.get(&root) {
+        if let Some(diags) = self.diagnostics.get(&url) {
             self.collect_code_lens(diags)
         } else {
             Ok(vec![])
@@ -292,3 +487,12 @@ impl File {
         })
     }
 }
+
+fn to_diagnostic_severity(severity: Severity) -> Option<DiagnosticSeverity> {
+    match severity {
+        Severity::None => None,
+        Severity::Advice => Some(DiagnosticSeverity::INFORMATION),
+        Severity::Warning => Some(DiagnosticSeverity::WARNING),
+        Severity::Error => Some(DiagnosticSeverity::ERROR),
+    }
+}
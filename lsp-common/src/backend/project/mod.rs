@@ -1,18 +1,33 @@
-use crate::backend::project::publisher::{Category, DiagnosticPublisher};
-use seedwing_enforcer_common::{config::FILE_NAME_YAML, enforcer::Enforcer, utils::pool::Pool};
+use crate::backend::{
+    build::Builds,
+    project::publisher::{Category, DiagnosticPublisher},
+};
+use seedwing_enforcer_common::{
+    config::{FILE_NAME_EXCEPTIONS_YAML, FILE_NAME_YAML},
+    enforcer::{source::AutoSource, Enforcer},
+    utils::{pool::Pool, process::Trust},
+};
 use std::{
     collections::HashMap,
     ffi::OsStr,
     path::{Path, PathBuf},
 };
 use tower_lsp::{
-    lsp_types::{CodeActionContext, CodeActionOrCommand, CodeLens, Range},
+    lsp_types::{
+        CodeActionContext, CodeActionOrCommand, CodeLens, DidChangeWatchedFilesRegistrationOptions,
+        FileSystemWatcher, GlobPattern, OneOf, Range, Registration, RelativePattern,
+        Unregistration,
+    },
     Client,
 };
+use url::Url;
 
 mod file;
 mod publisher;
 
+/// The method of watching files, registered for files the client doesn't watch on its own
+const WATCHED_FILES: &str = "workspace/didChangeWatchedFiles";
+
 // FIXME: when we get dropped, send out notifications for the open diagnostics
 // TODO: better management of diagnostics
 // FIXME: we need to do a lot more caching
@@ -23,43 +38,80 @@ pub struct Project {
     client: Client,
     root: PathBuf,
     enforcer: Enforcer,
+    builds: Builds,
+    trust: Trust,
 
     /// publisher for diagnostic information
     publisher: DiagnosticPublisher,
 
     /// File which we track for enforcing (not the configuration)
     files: HashMap<PathBuf, file::File>,
+    /// Watchers registered with the client, for the policy data and the SBOM
+    watchers: Vec<FileSystemWatcher>,
 }
 
 impl Project {
-    pub async fn new(client: Client, root: PathBuf, pool: Pool) -> Self {
+    pub async fn new(
+        client: Client,
+        root: PathBuf,
+        pool: Pool,
+        builds: Builds,
+        trust: Trust,
+    ) -> Self {
         let enforcer = Enforcer::new(&root, pool).await;
         let publisher = DiagnosticPublisher::new(client.clone());
         let mut result = Self {
             client,
             root,
             enforcer,
+            builds,
+            trust,
             publisher,
             files: Default::default(),
+            watchers: Default::default(),
         };
 
-        result.initial_scan().await;
         result.reconfigure().await;
 
         result
     }
 
-    /// Perform an initial scan
-    async fn initial_scan(&mut self) {
-        let pom = self.root.join("pom.xml");
-        if pom.is_file() {
+    /// Scan for the files to enforce
+    ///
+    /// This will add the manifest of the configured (or detected) project type, and drop all
+    /// others.
+    async fn scan(&mut self) {
+        let config = self.enforcer.evaluator.get_config().await;
+        let sbom = config
+            .as_ref()
+            .and_then(|config| config.enforcer.sbom.clone());
+        let r#type = config
+            .and_then(|config| config.enforcer.source)
+            .or_else(|| AutoSource::detect(&self.root));
+
+        let Some(r#type) = r#type else {
+            self.files.clear();
+            return;
+        };
+
+        let manifest = r#type.manifest_path(&self.root);
+        self.files.retain(|path, _| path == &manifest);
+
+        if !self.files.contains_key(&manifest) {
+            log::info!("Adding: {}", manifest.display());
             let file = file::File::new(
-                pom.clone(),
+                &self.root,
+                r#type,
                 self.client.clone(),
                 self.enforcer.evaluator.clone(),
+                self.builds.clone(),
+                self.trust,
             );
-            log::info!("Initially adding: {}", pom.display());
-            self.files.insert(pom, file);
+            self.files.insert(manifest.clone(), file);
+        }
+
+        if let (Some(sbom), Some(file)) = (sbom, self.files.get_mut(&manifest)) {
+            file.track(sbom.into());
         }
     }
 
@@ -67,48 +119,131 @@ impl Project {
     pub async fn changed(&mut self, path: &Path) {
         log::info!("Project file changed: {}", path.display());
 
-        if let Some(file) = self.files.get_mut(path) {
+        if let Some(file) = self.files.values_mut().find(|file| file.tracks(path)) {
             // content changed
             file.build(&mut self.publisher).await;
-        } else if path.ends_with("pom.xml") {
-            log::info!("Adding: {}", path.display());
-            // FIXME: don't descend into sub-dirs, only root level pom
-            let mut file = file::File::new(
-                path.into(),
-                self.client.clone(),
-                self.enforcer.evaluator.clone(),
-            );
-            file.build(&mut self.publisher).await;
-            self.files.insert(path.to_path_buf(), file);
-        } else if path.ends_with(FILE_NAME_YAML) {
+        } else if path.ends_with(FILE_NAME_YAML) || path.ends_with(FILE_NAME_EXCEPTIONS_YAML) {
             // configuration changed
             self.reconfigure().await;
         } else if matches!(path.extension().and_then(OsStr::to_str), Some("dog")) {
             // policy changed
             // TODO: we reconfigure on any .dog change, we could limit this to the ones used
             self.reconfigure().await;
+        } else if self.is_data(path).await {
+            // policy data changed
+            self.reconfigure().await;
+        } else if path.parent() == Some(self.root.as_path()) && AutoSource::detect(path).is_some() {
+            // a new manifest showed up
+            self.scan().await;
+            if let Some(file) = self.files.get_mut(path) {
+                file.build(&mut self.publisher).await;
+            }
         }
     }
 
+    /// Check if a file is part of the configured policy data
+    async fn is_data(&self, path: &Path) -> bool {
+        self.enforcer
+            .evaluator
+            .get_config()
+            .await
+            .and_then(|config| config.dependencies)
+            .map(|deps| deps.data.iter().any(|data| path.starts_with(data)))
+            .unwrap_or_default()
+    }
+
     async fn reconfigure(&mut self) {
         self.enforcer.configure().await;
 
         let diags = self.enforcer.diagnostics().await;
         self.publisher.publish_file(Category::Enforcer, diags).await;
 
+        // the configuration might have changed the project type
+        self.scan().await;
+        self.watch().await;
+
         // now re-evaluate all
         for file in &mut self.files.values_mut() {
             file.build(&mut self.publisher).await;
         }
     }
 
+    /// Register watchers for the configured policy data directories, and the SBOM
+    ///
+    /// The client only watches the well known files, the locations of the data and the SBOM are
+    /// only known from the configuration.
+    async fn watch(&mut self) {
+        let config = self.enforcer.evaluator.get_config().await;
+
+        // the base directory, and the pattern of the files in there
+        let mut patterns = vec![];
+        if let Some(config) = config {
+            for data in config.dependencies.iter().flat_map(|deps| &deps.data) {
+                patterns.push((PathBuf::from(data), "**/*".to_string()));
+            }
+            if let Some(sbom) = config.enforcer.sbom.map(PathBuf::from) {
+                if let (Some(parent), Some(name)) = (sbom.parent(), sbom.file_name()) {
+                    patterns.push((parent.into(), name.to_string_lossy().into()));
+                }
+            }
+        }
+
+        let watchers = patterns
+            .into_iter()
+            .filter_map(|(base, pattern)| Some((Url::from_directory_path(base).ok()?, pattern)))
+            .map(|(base, pattern)| FileSystemWatcher {
+                glob_pattern: GlobPattern::Relative(RelativePattern {
+                    base_uri: OneOf::Right(base),
+                    pattern,
+                }),
+                kind: None,
+            })
+            .collect::<Vec<_>>();
+
+        if watchers == self.watchers {
+            return;
+        }
+
+        let id = format!("{WATCHED_FILES}:{}", self.root.display());
+
+        if !self.watchers.is_empty() {
+            let unregistration = Unregistration {
+                id: id.clone(),
+                method: WATCHED_FILES.into(),
+            };
+            if let Err(err) = self
+                .client
+                .unregister_capability(vec![unregistration])
+                .await
+            {
+                log::warn!("Failed to unregister file watchers: {err}");
+            }
+        }
+
+        if !watchers.is_empty() {
+            let registration = Registration {
+                id,
+                method: WATCHED_FILES.into(),
+                register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions {
+                    watchers: watchers.clone(),
+                })
+                .ok(),
+            };
+            if let Err(err) = self.client.register_capability(vec![registration]).await {
+                log::warn!("Failed to register file watchers: {err}");
+            }
+        }
+
+        self.watchers = watchers;
+    }
+
     pub fn has_marker(path: &Path) -> bool {
         path.join(FILE_NAME_YAML).is_file()
     }
 
     pub async fn code_lens(&self, path: &Path) -> anyhow::Result<Vec<CodeLens>> {
-        if let Some(file) = self.files.get(path) {
-            file.code_lens().await
+        if let Some(file) = self.files.values().find(|file| file.tracks(path)) {
+            file.code_lens(path).await
         } else {
             Ok(vec![])
         }
@@ -120,7 +255,7 @@ impl Project {
         range: &Range,
         context: &CodeActionContext,
     ) -> anyhow::Result<Vec<CodeActionOrCommand>> {
-        if let Some(file) = self.files.get(path) {
+        if let Some(file) = self.files.values().find(|file| file.tracks(path)) {
             file.code_action(range, context).await
         } else {
             Ok(vec![])
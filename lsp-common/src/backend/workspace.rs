@@ -1,5 +1,5 @@
-use crate::backend::project::Project;
-use seedwing_enforcer_common::utils::pool::Pool;
+use crate::backend::{build::Builds, project::Project};
+use seedwing_enforcer_common::utils::{pool::Pool, process::Trust};
 use std::{
     collections::{hash_map::Entry, HashMap},
     path::{Path, PathBuf},
@@ -21,12 +21,15 @@ fn as_path(url: &Url) -> Option<PathBuf> {
 /// A workspace, managing attached folders
 pub struct Workspace {
     inner: Arc<RwLock<Inner>>,
+    builds: Builds,
 }
 
 impl Workspace {
     pub fn new(client: Client) -> Self {
+        let builds = Builds::default();
         Self {
-            inner: Arc::new(RwLock::new(Inner::new(client))),
+            inner: Arc::new(RwLock::new(Inner::new(client, builds.clone()))),
+            builds,
         }
     }
 
@@ -42,7 +45,16 @@ impl Workspace {
             .await
     }
 
+    /// Set the trust of the workspace, which applies to folders added afterwards
+    pub async fn set_trust(&self, trust: Trust) {
+        self.inner.write().await.trust = trust;
+    }
+
     pub async fn changed(&self, path: &Url) {
+        // a running build would hold the lock until it's done
+        if let Some(path) = as_path(path) {
+            self.builds.cancel(&path);
+        }
         self.inner.write().await.changed(path).await;
     }
 
@@ -68,12 +80,16 @@ struct Inner {
     client: Client,
     folders: HashMap<PathBuf, Folder>,
     pool: Pool,
+    builds: Builds,
+    trust: Trust,
 }
 
 impl Inner {
-    pub fn new(client: Client) -> Self {
+    pub fn new(client: Client, builds: Builds) -> Self {
         Self {
             pool: Pool::new(),
+            builds,
+            trust: Trust::default(),
             client,
             folders: Default::default(),
         }
@@ -100,7 +116,14 @@ impl Inner {
                 log::info!("Add folder: {}", path.display());
                 self.folders.insert(
                     path.to_path_buf(),
-                    Folder::new(self.client.clone(), path, self.pool.clone()).await,
+                    Folder::new(
+                        self.client.clone(),
+                        path,
+                        self.pool.clone(),
+                        self.builds.clone(),
+                        self.trust,
+                    )
+                    .await,
                 );
             }
         }
@@ -167,15 +190,25 @@ pub struct Folder {
     client: Client,
     root: PathBuf,
     pool: Pool,
+    builds: Builds,
+    trust: Trust,
     projects: HashMap<PathBuf, Project>,
 }
 
 impl Folder {
-    pub async fn new(client: Client, root: PathBuf, pool: Pool) -> Self {
+    pub async fn new(
+        client: Client,
+        root: PathBuf,
+        pool: Pool,
+        builds: Builds,
+        trust: Trust,
+    ) -> Self {
         let mut result = Self {
             client,
             root,
             pool,
+            builds,
+            trust,
             projects: Default::default(),
         };
         result.scan().await;
@@ -224,7 +257,14 @@ impl Folder {
                 // add
                 log::info!("Add new project: {}", path.display());
                 entry.insert(
-                    Project::new(self.client.clone(), path.into(), self.pool.clone()).await,
+                    Project::new(
+                        self.client.clone(),
+                        path.into(),
+                        self.pool.clone(),
+                        self.builds.clone(),
+                        self.trust,
+                    )
+                    .await,
                 );
             }
             (Entry::Occupied(entry), false) => {
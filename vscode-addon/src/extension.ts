@@ -60,19 +60,49 @@ async function startLsp(context: ExtensionContext): Promise<LanguageClient> {
     const clientOptions: LanguageClientOptions = {
         documentSelector: [
             {scheme: "file", pattern: "**/.enforcer.yaml"},
-            {scheme: "file", pattern: "**/pom.xml"}
+            {scheme: "file", pattern: "**/.enforcer-exceptions.yaml"},
+            {scheme: "file", pattern: "**/pom.xml"},
+            {scheme: "file", pattern: "**/Cargo.toml"},
+            {scheme: "file", pattern: "**/package.json"},
+            {scheme: "file", pattern: "**/pyproject.toml"},
+            {scheme: "file", pattern: "**/Pipfile"},
+            {scheme: "file", pattern: "**/requirements*.txt"},
+            {scheme: "file", pattern: "**/go.mod"},
+            {scheme: "file", pattern: "**/build.gradle{,.kts}"},
+            {scheme: "file", pattern: "**/libs.versions.toml"}
         ],
         synchronize: {
             fileEvents: [
                 workspace.createFileSystemWatcher("**/pom.xml"),
+                workspace.createFileSystemWatcher("**/.mvn/**"),
+                workspace.createFileSystemWatcher("**/Cargo.toml"),
+                workspace.createFileSystemWatcher("**/Cargo.lock"),
+                workspace.createFileSystemWatcher("**/package{,-lock}.json"),
+                workspace.createFileSystemWatcher("**/{yarn.lock,pnpm-lock.yaml}"),
+                workspace.createFileSystemWatcher("**/pyproject.toml"),
+                workspace.createFileSystemWatcher("**/{poetry,uv}.lock"),
+                workspace.createFileSystemWatcher("**/Pipfile{,.lock}"),
+                workspace.createFileSystemWatcher("**/requirements*.txt"),
+                workspace.createFileSystemWatcher("**/go.{mod,sum}"),
+                workspace.createFileSystemWatcher("**/{build,settings}.gradle{,.kts}"),
+                workspace.createFileSystemWatcher("**/*.lockfile"),
+                workspace.createFileSystemWatcher("**/gradle.properties"),
+                workspace.createFileSystemWatcher("**/gradle/**"),
                 workspace.createFileSystemWatcher("**/.enforcer.yaml"),
-                workspace.createFileSystemWatcher("**/*.dog")
+                workspace.createFileSystemWatcher("**/.enforcer-exceptions.yaml"),
+                workspace.createFileSystemWatcher("**/*.dog"),
+                // the policy data directories, and an SBOM used as source, are registered by the
+                // server, from the configuration
             ],
         },
         markdown: {
             isTrusted: true,
             supportHtml: true
         },
+        // build tools of projects (like Maven) only run in trusted workspaces
+        initializationOptions: () => ({
+            trusted: workspace.isTrusted,
+        }),
         outputChannel,
         outputChannelName: "Seedwing Enforcer",
         traceOutputChannel,
@@ -132,6 +162,11 @@ export async function activate(context: ExtensionContext): Promise<void> {
     // start client
 
     await client.start();
+
+    // restart, running the build tools, once the workspace gets trusted
+    context.subscriptions.push(
+        workspace.onDidGrantWorkspaceTrust(() => client.restart())
+    );
 }
 
 export function deactivate(): Thenable<void> | undefined {